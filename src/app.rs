@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
@@ -5,13 +7,26 @@ use std::process;
 
 use home::home_dir;
 use iced::{
-    button, executor, image::Handle, slider, text_input, time, Align, Application, Button,
-    Checkbox, Clipboard, Color, Column, Command, Container, Element, Image, Length, Radio, Row,
-    Slider, Space, Text, TextInput,
+    button, executor, image::Handle, pick_list, scrollable, slider, text_input, time, Align,
+    Application, Button, Checkbox, Clipboard, Color, Column, Command, Container, Element, Image,
+    Length, PickList, Radio, Row, Scrollable, Slider, Space, Text, TextInput,
 };
+use image::RgbaImage;
 
+use crate::backup::{self, Backup};
 use crate::config;
+use crate::ledger;
+use crate::pack_stack::{resolve_dependencies, PackDeps, PackStack};
+use crate::resource_pack::{
+    self, Compression, PackDiff, PackFormat, PackRef, ResourcePack, SignatureStatus,
+    PREVIEW_HEIGHT, THUMBNAIL_SIZE,
+};
+use crate::snapshot::{self, GameChanges};
 use crate::style;
+use crate::trash::{self, DeletedWorld};
+use crate::trust::{self, TrustStore};
+use crate::world_archive::{self, ImportError, WorldInfo};
+use crate::worlds::{self, World};
 use crate::CFG_PATH;
 use crate::VERSION;
 
@@ -22,9 +37,13 @@ pub struct App {
     theme: style::Theme,
 
     // Ui Elements
+    launch_status: String,
     launch_button: button::State,
     volume_slider: slider::State,
     settings_button: button::State,
+    pack_pick_list: pick_list::State<PackChoice>,
+    packs_button: button::State,
+    worlds_button: button::State,
 
     // Settings
     save_button: button::State,
@@ -32,12 +51,159 @@ pub struct App {
     reset_button: button::State,
 
     game_path_text: text_input::State,
+    save_path_text: text_input::State,
+    trash_days_slider: slider::State,
+    backup_count_slider: slider::State,
+    compression_pick_list: pick_list::State<Compression>,
+
+    // Resource Packs
+    packs: Vec<PackEntry>,
+    /// Decoded thumbnails and previews, by pack hash
+    pack_images: BTreeMap<String, PackImages>,
+    pack_status: String,
+    /// Pack waiting for its delete to be confirmed
+    pack_delete: Option<String>,
+    pack_import_path: String,
+    pack_list: scrollable::State,
+    pack_import_text: text_input::State,
+    pack_import_button: button::State,
+    pack_create_name: String,
+    pack_create_author: String,
+    pack_create_sign: bool,
+    pack_create_name_text: text_input::State,
+    pack_create_author_text: text_input::State,
+    pack_create_button: button::State,
+    pack_merge_button: button::State,
+    packs_apply_button: button::State,
+    packs_reset_button: button::State,
+    packs_exit_button: button::State,
+
+    // Pack Diff
+    pack_diff: Option<(String, PackDiff)>,
+    pack_diff_status: String,
+    pack_diff_list: scrollable::State,
+    pack_diff_export_button: button::State,
+    pack_diff_exit_button: button::State,
+
+    // Worlds
+    worlds: Vec<WorldEntry>,
+    world_status: String,
+    world_list: scrollable::State,
+    world_default_button: button::State,
+    world_create_name: String,
+    world_template: TemplateChoice,
+    world_create_text: text_input::State,
+    world_template_pick_list: pick_list::State<TemplateChoice>,
+    world_create_button: button::State,
+    world_launch_button: button::State,
+    world_export_note: String,
+    world_export_text: text_input::State,
+    world_import_path: String,
+    world_import_text: text_input::State,
+    world_import_button: button::State,
+    world_replace_button: button::State,
+    /// The imported world has the same name as one already there
+    world_import_exists: bool,
+    worlds_deleted_button: button::State,
+    worlds_backups_button: button::State,
+    worlds_exit_button: button::State,
+
+    // World Backups
+    backups: Vec<BackupEntry>,
+    backup_list: scrollable::State,
+    backups_exit_button: button::State,
+
+    // Deleted Worlds
+    deleted: Vec<DeletedEntry>,
+    deleted_list: scrollable::State,
+    deleted_exit_button: button::State,
+
+    // Stale default.tar
+    game_changes: Option<GameChanges>,
+    default_status: String,
+    default_rebuild_button: button::State,
+    default_keep_button: button::State,
 }
 
 #[derive(Debug)]
 pub enum View {
     Main,
     Settings,
+    ResourcePacks,
+    PackDiff,
+    StaleDefault,
+    Worlds,
+    DeletedWorlds,
+    Backups,
+}
+
+#[derive(Debug, Default)]
+struct PackEntry {
+    file: String,
+    name: String,
+    author: String,
+    version: String,
+    description: Option<String>,
+    signature: Option<SignatureStatus>,
+    requires: Vec<PackRef>,
+    conflicts: Vec<PackRef>,
+    /// Unpacked folder packs can be signed in place
+    folder: bool,
+    thumbnail: Option<Handle>,
+    previews: Vec<Handle>,
+    image_mismatches: Vec<String>,
+    error: Option<String>,
+
+    raise_button: button::State,
+    diff_button: button::State,
+    verify_button: button::State,
+    trust_button: button::State,
+    sign_button: button::State,
+    delete_button: button::State,
+}
+
+/// A pack's thumbnail and previews, decoded in the background
+#[derive(Debug, Clone)]
+pub struct PackImages {
+    thumbnail: Option<Handle>,
+    previews: Vec<Handle>,
+    /// Images that don't match the game's
+    mismatches: Vec<String>,
+}
+
+#[derive(Debug)]
+struct WorldEntry {
+    world: World,
+    select_button: button::State,
+    export_button: button::State,
+    delete_button: button::State,
+}
+
+#[derive(Debug)]
+struct BackupEntry {
+    backup: Backup,
+    restore_button: button::State,
+}
+
+#[derive(Debug)]
+struct DeletedEntry {
+    world: DeletedWorld,
+    restore_button: button::State,
+    purge_button: button::State,
+}
+
+/// Save a new world is copied from
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum TemplateChoice {
+    #[default]
+    None,
+    World(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackChoice {
+    None,
+    Pack(String),
 }
 
 #[derive(Debug, Clone)]
@@ -49,6 +215,54 @@ pub enum Message {
     ConfigSave,
     ConfigExit,
     ConfigReset,
+    OpenResourcePacks,
+    OpenWorlds,
+    WorldSelect(usize),
+    WorldSelectDefault,
+    WorldCreateName(String),
+    WorldTemplate(TemplateChoice),
+    /// Make the new world, launching it straight away if set
+    WorldCreate(bool),
+    WorldDelete(usize),
+    WorldExportNote(String),
+    WorldExport(usize),
+    WorldImportPath(String),
+    /// Import a world, replacing one with the same name if set
+    WorldImport(bool),
+    OpenDeletedWorlds,
+    DeletedRestore(usize),
+    DeletedPurge(usize),
+    DeletedExit,
+    OpenBackups,
+    BackupRestore(usize),
+    BackupsExit,
+    WorldsExit,
+    PacksExit,
+    PackSelect(PackChoice),
+    PackToggle(usize, bool),
+    PackRaise(usize),
+    /// Ask before deleting a pack, then delete it once confirmed
+    PackDelete(usize, bool),
+    PackDiff(usize),
+    PackVerify(usize),
+    PackTrust(usize),
+    PackUntrust(usize),
+    PackSign(usize),
+    PackDiffExport,
+    PackDiffExit,
+    /// Pack images loaded in the background, as (file, pack hash, images if not cached)
+    PackImages(Vec<(String, String, Option<PackImages>)>),
+    PackImportPath(String),
+    PackImport,
+    PackCreateName(String),
+    PackCreateAuthor(String),
+    PackCreateSign(bool),
+    PackCreate,
+    PackMerge,
+    PacksApply,
+    PacksReset,
+    DefaultRebuild,
+    DefaultKeep,
 }
 
 impl Application for App {
@@ -71,7 +285,7 @@ impl Application for App {
         );
         let config = config::Config::load(config_path.join("config.cfg"));
 
-        let app = match config {
+        let mut app = match config {
             Some(config) => {
                 println!("[✅]");
                 App {
@@ -84,8 +298,28 @@ impl Application for App {
                 App::default()
             }
         };
+        let command = app.refresh_packs();
 
-        (app, Command::none())
+        let purged = trash::auto_purge(&config_path, app.config.trash_days);
+        if purged > 0 {
+            println!(
+                "[*] Purged {} world(s) deleted over {} days ago",
+                purged, app.config.trash_days
+            );
+        }
+
+        print!("[*] Checking default.tar is up to date ");
+        match snapshot::check(&config_path, &app.config.game_path) {
+            Some(i) => {
+                println!("[❌]");
+                println!("[!] The game has changed since default.tar was made: {}", i);
+                app.game_changes = Some(i);
+                app.view = View::StaleDefault;
+            }
+            None => println!("[✅]"),
+        }
+
+        (app, command)
     }
 
     fn title(&self) -> String {
@@ -93,27 +327,16 @@ impl Application for App {
     }
 
     fn update(&mut self, message: Message, _clipboard: &mut Clipboard) -> Command<Message> {
+        let mut command = Command::none();
+
         #[allow(unreachable_patterns)]
         match message {
             Message::Tick => {}
             Message::Launch => {
-                println!(
-                    "[*] Updateing Game Config (FullScreen: {}, Volume: {})",
-                    self.config.full_screen, self.config.volume
-                );
-
-                fs::write(
-                    self.config.game_path.join("freeways.cfg"),
-                    format!(
-                        r#"<Freeways fullScreen="{}" screenWidth="0" useFBO="false" volume="{}"/>{}"#,
-                        self.config.full_screen, self.config.volume, "\n"
-                    ),
-                )
-                .unwrap();
-
-                let path = self.config.game_path.join("Freeways.exe");
-                println!("[*] Launching Game ({})", path.to_string_lossy());
-                process::Command::new(path).output().unwrap();
+                self.launch_status = match self.launch() {
+                    Ok(_) => String::new(),
+                    Err(e) => e,
+                };
             }
 
             Message::SettingsUpdate(config_update) => {
@@ -146,11 +369,659 @@ impl Application for App {
                 self.config = config::Config::default();
             }
 
+            Message::OpenResourcePacks => {
+                command = self.refresh_packs();
+                self.pack_status = String::new();
+                self.pack_delete = None;
+                self.view = View::ResourcePacks;
+            }
+
+            Message::OpenWorlds => {
+                self.refresh_worlds();
+                self.world_status = String::new();
+                self.view = View::Worlds;
+            }
+
+            Message::WorldSelect(i) => {
+                self.config.world = Some(self.worlds[i].world.path.clone());
+                self.save_config();
+                self.view = View::Main;
+            }
+
+            Message::WorldSelectDefault => {
+                self.config.world = None;
+                self.save_config();
+                self.view = View::Main;
+            }
+
+            Message::WorldCreateName(name) => {
+                self.world_create_name = name;
+            }
+
+            Message::WorldTemplate(template) => {
+                self.world_template = template;
+            }
+
+            Message::WorldCreate(launch) => {
+                println!("[*] Creating World ({})", self.world_create_name);
+
+                let dir =
+                    worlds::save_folder(&self.config.game_path, self.config.save_path.as_deref());
+                let template = match &self.world_template {
+                    TemplateChoice::World(i) => Some(i.as_path()),
+                    TemplateChoice::None => None,
+                };
+                match worlds::create_world(&dir, &self.world_create_name, template) {
+                    Ok(world) => {
+                        self.world_status = format!("Created {}", world.name);
+                        self.world_create_name = String::new();
+                        self.config.world = Some(world.path);
+                        self.save_config();
+                        self.refresh_worlds();
+
+                        if launch {
+                            self.view = View::Main;
+                            self.launch_status = match self.launch() {
+                                Ok(_) => String::new(),
+                                Err(e) => e,
+                            };
+                        }
+                    }
+                    Err(e) => self.world_status = format!("Failed to create world: {}", e),
+                }
+            }
+
+            Message::WorldDelete(i) => {
+                let world = self.worlds[i].world.clone();
+                print!("[*] Deleting World ({}) ", world.name);
+
+                match trash::delete_world(&data_path(), &world) {
+                    Ok(_) => {
+                        println!("[✅]");
+                        self.world_status = format!("Moved {} to the deleted worlds", world.name);
+                        if self.config.world.as_ref() == Some(&world.path) {
+                            self.config.world = None;
+                            self.save_config();
+                        }
+                    }
+                    Err(e) => {
+                        println!("[❌]");
+                        self.world_status = e;
+                    }
+                }
+                self.refresh_worlds();
+            }
+
+            Message::WorldExportNote(note) => {
+                self.world_export_note = note;
+            }
+
+            Message::WorldExport(i) => {
+                let world = self.worlds[i].world.clone();
+                print!("[*] Exporting World ({}) ", world.name);
+
+                self.world_status = match world_archive::export_world(
+                    &data_path(),
+                    &world,
+                    self.pack_names(),
+                    &self.world_export_note,
+                ) {
+                    Ok(i) => {
+                        println!("[✅]");
+                        self.world_export_note = String::new();
+                        format!("Exported {} to {}", world.name, i.to_string_lossy())
+                    }
+                    Err(e) => {
+                        println!("[❌]");
+                        format!("Failed to export {}: {}", world.name, e)
+                    }
+                };
+            }
+
+            Message::WorldImportPath(path) => {
+                self.world_import_path = path;
+                self.world_import_exists = false;
+            }
+
+            Message::WorldImport(replace) => {
+                print!("[*] Importing World ({}) ", self.world_import_path);
+
+                let dir =
+                    worlds::save_folder(&self.config.game_path, self.config.save_path.as_deref());
+                self.world_import_exists = false;
+                self.world_status = match world_archive::import_world(
+                    &data_path(),
+                    Path::new(&self.world_import_path),
+                    &dir,
+                    replace,
+                ) {
+                    Ok((world, info)) => {
+                        println!("[✅]");
+                        self.world_import_path = String::new();
+                        import_text(&world, &info)
+                    }
+                    Err(ImportError::Exists(name)) => {
+                        println!("[❌]");
+                        self.world_import_exists = true;
+                        format!(
+                            "A world named {} already exists, replace it to import anyway",
+                            name
+                        )
+                    }
+                    Err(e) => {
+                        println!("[❌]");
+                        format!("Failed to import: {}", e)
+                    }
+                };
+                self.refresh_worlds();
+            }
+
+            Message::OpenDeletedWorlds => {
+                self.refresh_deleted();
+                self.view = View::DeletedWorlds;
+            }
+
+            Message::DeletedRestore(i) => {
+                let world = self.deleted[i].world.clone();
+                print!("[*] Restoring World ({}) ", world.name);
+
+                self.world_status = match world.restore(&self.save_dirs()) {
+                    Ok(_) => {
+                        println!("[✅]");
+                        format!("Restored {}", world.name)
+                    }
+                    Err(e) => {
+                        println!("[❌]");
+                        format!("Failed to restore {}: {}", world.name, e)
+                    }
+                };
+                self.refresh_deleted();
+            }
+
+            Message::DeletedPurge(i) => {
+                let world = self.deleted[i].world.clone();
+                print!("[*] Purging Deleted World ({}) ", world.name);
+
+                self.world_status = match world.purge() {
+                    Some(_) => {
+                        println!("[✅]");
+                        format!("Deleted {} for good", world.name)
+                    }
+                    None => {
+                        println!("[❌]");
+                        format!("Failed to delete {}", world.name)
+                    }
+                };
+                self.refresh_deleted();
+            }
+
+            Message::OpenBackups => {
+                self.refresh_backups();
+                self.world_status = String::new();
+                self.view = View::Backups;
+            }
+
+            Message::BackupRestore(i) => {
+                let backup = self.backups[i].backup.clone();
+                print!("[*] Restoring Backup ({}) ", backup.path.to_string_lossy());
+
+                self.world_status = match backup.restore(&data_path(), &self.save_dirs()) {
+                    Ok(count) => {
+                        println!("[✅]");
+                        format!(
+                            "Restored {} file(s) from {}, the replaced files were backed up",
+                            count,
+                            worlds::time_text(backup.time())
+                        )
+                    }
+                    Err(e) => {
+                        println!("[❌]");
+                        format!("Failed to restore the backup: {}", e)
+                    }
+                };
+                self.refresh_backups();
+            }
+
+            Message::BackupsExit => {
+                self.refresh_worlds();
+                self.view = View::Worlds;
+            }
+
+            Message::DeletedExit => {
+                self.refresh_worlds();
+                self.view = View::Worlds;
+            }
+
+            Message::WorldsExit => {
+                self.view = View::Main;
+            }
+
+            Message::PacksExit => {
+                self.view = View::Main;
+            }
+
+            Message::PackSelect(choice) => {
+                if let PackChoice::Pack(file) = choice {
+                    let mut packs = self.config.resource_packs.clone();
+                    packs.retain(|x| *x != file);
+                    packs.insert(0, file);
+                    match resolve_dependencies(&self.pack_deps(), &packs) {
+                        Ok(i) => self.config.resource_packs = i,
+                        Err(e) => self.pack_status = format!("Can't enable {}: {}", packs[0], e),
+                    }
+                } else {
+                    self.config.resource_packs.clear();
+                }
+                self.save_config();
+            }
+
+            Message::PackToggle(i, enabled) => {
+                let file = self.packs[i].file.clone();
+                let name = self.packs[i].name.clone();
+                let mut packs = self.config.resource_packs.clone();
+                packs.retain(|x| *x != file);
+
+                if enabled {
+                    packs.push(file.clone());
+                    match resolve_dependencies(&self.pack_deps(), &packs) {
+                        Ok(i) => {
+                            let added = i
+                                .iter()
+                                .filter(|x| !packs.contains(x))
+                                .cloned()
+                                .collect::<Vec<_>>();
+                            if !added.is_empty() {
+                                self.pack_status = format!(
+                                    "Also enabled {}, needed by {}",
+                                    added.join(", "),
+                                    name
+                                );
+                            }
+                            self.config.resource_packs = i;
+                        }
+                        Err(e) => self.pack_status = format!("Can't enable {}: {}", file, e),
+                    }
+                } else {
+                    let needed_by = self
+                        .packs
+                        .iter()
+                        .filter(|x| packs.contains(&x.file))
+                        .filter(|x| x.requires.iter().any(|x| x.is_named(&name)))
+                        .map(|x| x.name.to_owned())
+                        .collect::<Vec<_>>();
+                    if needed_by.is_empty() {
+                        self.config.resource_packs = packs;
+                    } else {
+                        self.pack_status = format!(
+                            "Can't disable {}, it is needed by {}",
+                            name,
+                            needed_by.join(", ")
+                        );
+                    }
+                }
+                self.save_config();
+            }
+
+            Message::PackRaise(i) => {
+                let deps = self.pack_deps();
+                let packs = &mut self.config.resource_packs;
+                if let Some(pos) = packs.iter().position(|x| *x == self.packs[i].file) {
+                    if pos > 0 {
+                        packs.swap(pos, pos - 1);
+                    }
+                }
+                // Packs can't be raised above the packs that need them
+                if let Ok(i) = resolve_dependencies(&deps, packs) {
+                    *packs = i;
+                }
+                self.save_config();
+            }
+
+            Message::PackDelete(i, false) => {
+                self.pack_delete = Some(self.packs[i].file.clone());
+                self.pack_status = format!(
+                    "Delete {} for good? Its files are uninstalled first",
+                    self.packs[i].file
+                );
+            }
+
+            Message::PackDelete(i, true) => {
+                let file = self.packs[i].file.clone();
+                let name = self.packs[i].name.clone();
+                self.pack_delete = None;
+                println!("[*] Deleting Resource Pack ({})", file);
+
+                // Its files would otherwise stay in the game with nothing to remove them
+                let game_path = &self.config.game_path;
+                let installed = ledger::Ledger::load(&data_path(), game_path)
+                    .packs
+                    .iter()
+                    .any(|x| x.name == name);
+                let uninstalled = if installed {
+                    ledger::transact(&data_path(), game_path, |ledger, transaction| {
+                        ledger.uninstall(transaction, &name)
+                    })
+                } else {
+                    Some(Vec::new())
+                };
+
+                self.pack_status = match uninstalled {
+                    Some(edited) => {
+                        match resource_pack::delete_pack(
+                            &data_path().join("resource_packs").join(&file),
+                        ) {
+                            Some(_) if edited.is_empty() => format!("Deleted {}", file),
+                            Some(_) => format!(
+                                "Deleted {}, these files were changed by hand and left alone: {}",
+                                file,
+                                edited.join(", ")
+                            ),
+                            None => format!("Failed to delete {}", file),
+                        }
+                    }
+                    None => format!("Failed to uninstall {}, it wasn't deleted", file),
+                };
+                self.config.resource_packs.retain(|x| *x != file);
+                self.save_config();
+                command = self.refresh_packs();
+            }
+
+            Message::PackDiff(i) => {
+                let file = self.packs[i].file.clone();
+                println!(
+                    "[*] Comparing Resource Pack ({}) with the original resources",
+                    file
+                );
+
+                let pack = ResourcePack::load(data_path().join("resource_packs").join(&file));
+                let default = resource_pack::load_default(&data_path());
+                match (pack, default) {
+                    (Ok(pack), Ok(default)) => match pack.diff(&default) {
+                        Some(diff) => {
+                            self.pack_diff = Some((file, diff));
+                            self.pack_diff_status = String::new();
+                            self.view = View::PackDiff;
+                        }
+                        None => self.pack_status = format!("Failed to compare {}", file),
+                    },
+                    (Err(e), _) => self.pack_status = format!("Failed to load {}: {}", file, e),
+                    (_, Err(e)) => {
+                        self.pack_status = format!("Failed to load the original resources: {}", e)
+                    }
+                }
+            }
+
+            Message::PackVerify(i) => {
+                let file = self.packs[i].file.clone();
+                println!("[*] Verifying Resource Pack ({})", file);
+
+                self.pack_status =
+                    match ResourcePack::load(data_path().join("resource_packs").join(&file)) {
+                        Ok(pack) if pack.manifest.is_none() => format!("{} has no manifest", file),
+                        Ok(pack) => match pack.verify() {
+                            Some(i) => format!("{}: {}", file, i),
+                            None => format!("Failed to read {}", file),
+                        },
+                        Err(e) => format!("Failed to load {}: {}", file, e),
+                    };
+            }
+
+            Message::PackTrust(i) => {
+                let file = self.packs[i].file.clone();
+                println!("[*] Trusting the key that signed {}", file);
+
+                let pack = ResourcePack::load(data_path().join("resource_packs").join(&file));
+                self.pack_status = match pack.as_ref().map(|x| (x, x.check_signature())) {
+                    Ok((pack, Ok(Some(key)))) => {
+                        let mut trust = TrustStore::load(&data_path());
+                        trust.trust(key, &pack.author);
+                        match trust.save() {
+                            Some(_) => format!("Trusted {}'s key", pack.author),
+                            None => "Failed to save the trusted keys".to_owned(),
+                        }
+                    }
+                    Ok(_) => format!("{} doesn't have a valid signature", file),
+                    Err(e) => format!("Failed to load {}: {}", file, e),
+                };
+                command = self.refresh_packs();
+            }
+
+            Message::PackUntrust(i) => {
+                let file = self.packs[i].file.clone();
+                println!("[*] Untrusting the key that signed {}", file);
+
+                let pack = ResourcePack::load(data_path().join("resource_packs").join(&file));
+                self.pack_status = match pack.as_ref().map(|x| x.check_signature()) {
+                    Ok(Ok(Some(key))) => {
+                        let mut trust = TrustStore::load(&data_path());
+                        let name = trust
+                            .get(key)
+                            .map(|x| x.name.to_owned())
+                            .unwrap_or_default();
+                        trust.untrust(key);
+                        match trust.save() {
+                            Some(_) => format!("Stopped trusting {}'s key", name),
+                            None => "Failed to save the trusted keys".to_owned(),
+                        }
+                    }
+                    Ok(_) => format!("{} doesn't have a valid signature", file),
+                    Err(e) => format!("Failed to load {}: {}", file, e),
+                };
+                command = self.refresh_packs();
+            }
+
+            Message::PackSign(i) => {
+                let file = self.packs[i].file.clone();
+                println!("[*] Signing Resource Pack ({})", file);
+
+                let pack = ResourcePack::load(data_path().join("resource_packs").join(&file));
+                self.pack_status = match (pack, trust::signing_key(&data_path())) {
+                    (Ok(pack), Some(key)) => match pack.sign(&key) {
+                        Ok(_) => format!("Signed {}", file),
+                        Err(e) => format!("Failed to sign {}: {}", file, e),
+                    },
+                    (Err(e), _) => format!("Failed to load {}: {}", file, e),
+                    (_, None) => "Failed to load the signing key".to_owned(),
+                };
+                command = self.refresh_packs();
+            }
+
+            Message::PackImages(images) => {
+                for (file, hash, images) in images {
+                    if let Some(i) = images {
+                        self.pack_images.insert(hash.to_owned(), i);
+                    }
+                    let images = self.pack_images.get(&hash);
+                    if let Some((entry, images)) =
+                        self.packs.iter_mut().find(|x| x.file == file).zip(images)
+                    {
+                        entry.thumbnail = images.thumbnail.clone();
+                        entry.previews = images.previews.clone();
+                        entry.image_mismatches = images.mismatches.clone();
+                    }
+                }
+            }
+
+            Message::PackDiffExport => {
+                if let Some((file, diff)) = &self.pack_diff {
+                    let path = data_path().join("diffs").join(format!("{}.json", file));
+                    print!("[*] Exporting Pack Diff ({}) ", path.to_string_lossy());
+
+                    self.pack_diff_status = match diff.save(&path) {
+                        Ok(_) => {
+                            println!("[✅]");
+                            format!("Saved to {}", path.to_string_lossy())
+                        }
+                        Err(e) => {
+                            println!("[❌]");
+                            format!("Failed to save the diff: {}", e)
+                        }
+                    };
+                }
+            }
+
+            Message::PackDiffExit => {
+                self.pack_diff = None;
+                self.view = View::ResourcePacks;
+            }
+
+            Message::PackImportPath(path) => {
+                self.pack_import_path = path;
+            }
+
+            Message::PackImport => {
+                println!("[*] Importing Resource Pack ({})", self.pack_import_path);
+
+                self.pack_status = match resource_pack::import_pack(
+                    Path::new(&self.pack_import_path),
+                    &data_path().join("resource_packs"),
+                ) {
+                    Ok(i) => {
+                        self.pack_import_path = String::new();
+                        format!("Imported {}", i)
+                    }
+                    Err(e) => format!("Failed to import: {}", e),
+                };
+                command = self.refresh_packs();
+            }
+
+            Message::PackCreateName(name) => {
+                self.pack_create_name = name;
+            }
+
+            Message::PackCreateAuthor(author) => {
+                self.pack_create_author = author;
+            }
+
+            Message::PackCreateSign(sign) => {
+                self.pack_create_sign = sign;
+            }
+
+            Message::PackCreate => {
+                println!(
+                    "[*] Creating Resource Pack from the data folder ({})",
+                    self.pack_create_name
+                );
+
+                self.pack_status = match resource_pack::create_pack(
+                    &data_path(),
+                    &self.config.game_path,
+                    &self.pack_create_name,
+                    &self.pack_create_author,
+                    self.pack_create_sign,
+                ) {
+                    Ok((file, count)) => {
+                        self.pack_create_name = String::new();
+                        format!("Created {} with {} changed file(s)", file, count)
+                    }
+                    Err(e) => format!("Failed to create pack: {}", e),
+                };
+                command = self.refresh_packs();
+            }
+
+            Message::PackMerge => {
+                println!(
+                    "[*] Merging Resource Packs ({}) into {}",
+                    self.config.resource_packs.join(", "),
+                    self.pack_create_name
+                );
+
+                let path = data_path().join("resource_packs");
+                self.pack_status =
+                    match resolve_dependencies(&self.pack_deps(), &self.config.resource_packs)
+                        .and_then(|x| {
+                            resource_pack::merge_packs(
+                                &data_path(),
+                                &PackStack::load(&path, &x)?.packs,
+                                &self.pack_create_name,
+                                &self.pack_create_author,
+                                self.pack_create_sign,
+                            )
+                        }) {
+                        Ok((file, count)) => {
+                            self.pack_create_name = String::new();
+                            format!(
+                                "Merged the enabled packs into {} with {} file(s)",
+                                file, count
+                            )
+                        }
+                        Err(e) => format!("Failed to merge packs: {}", e),
+                    };
+                command = self.refresh_packs();
+            }
+
+            Message::DefaultRebuild => {
+                print!("[*] Rebuilding default.tar ");
+
+                match snapshot::rebuild_default(
+                    &data_path(),
+                    &self.config.game_path,
+                    self.config.default_compression,
+                ) {
+                    Some(i) => {
+                        println!("[✅]");
+                        println!("[*] Archived the old default.tar as {}", i);
+                        self.game_changes = None;
+                        self.view = View::Main;
+
+                        // Image checks were against the old originals
+                        self.pack_images.clear();
+                        command = self.refresh_packs();
+                    }
+                    None => {
+                        println!("[❌]");
+                        self.default_status = "Failed to rebuild default.tar".to_owned();
+                    }
+                }
+            }
+
+            Message::DefaultKeep => {
+                self.game_changes = None;
+                self.view = View::Main;
+            }
+
+            Message::PacksApply => {
+                self.pack_status = match self.apply_packs() {
+                    Ok((edited, images)) => {
+                        let mut out = "Applied resource packs".to_owned();
+                        if !edited.is_empty() {
+                            out.push_str(&format!(
+                                ", left {} hand edited file(s) alone: {}",
+                                edited.len(),
+                                edited.join(", ")
+                            ));
+                        }
+                        if !images.is_empty() {
+                            out.push_str(&format!("\n{}", images.join("\n")));
+                        }
+                        out
+                    }
+                    Err(e) => format!("Failed to apply resource packs: {}", e),
+                };
+            }
+
+            Message::PacksReset => {
+                print!("[*] Resetting to original resources ");
+
+                match resource_pack::restore_default(&data_path(), &self.config.game_path) {
+                    Some(_) => {
+                        println!("[✅]");
+                        self.pack_status = "Restored the original game resources".to_owned();
+                        self.config.resource_packs.clear();
+                        self.save_config();
+                    }
+                    None => {
+                        println!("[❌]");
+                        self.pack_status = "Failed to restore the original resources".to_owned();
+                    }
+                }
+            }
+
             _ => {
                 panic!("Unhandled Event: {:?}", message);
             }
         };
-        Command::none()
+        command
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
@@ -202,13 +1073,61 @@ impl Application for App {
                             ),
                     )
                     .push(
-                        Row::new().spacing(10).push(
-                            Text::new("Resource Pack")
+                        Row::new()
+                            .spacing(10)
+                            .push(
+                                Text::new("Resource Pack")
+                                    .size(25)
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .push(
+                                PickList::new(
+                                    &mut self.pack_pick_list,
+                                    pack_choices(&self.packs),
+                                    Some(
+                                        self.config
+                                            .resource_packs
+                                            .first()
+                                            .map(|x| PackChoice::Pack(x.to_owned()))
+                                            .unwrap_or(PackChoice::None),
+                                    ),
+                                    Message::PackSelect,
+                                )
+                                .width(Length::FillPortion(3))
+                                .style(self.theme),
+                            )
+                            .push(
+                                Button::new(&mut self.packs_button, Text::new("Manage"))
+                                    .width(Length::FillPortion(1))
+                                    .on_press(Message::OpenResourcePacks)
+                                    .style(self.theme),
+                            ),
+                    )
+                    .push(
+                        Row::new()
+                            .spacing(10)
+                            .push(Text::new("World").size(25).width(Length::FillPortion(1)))
+                            .push(
+                                Text::new(
+                                    self.config
+                                        .world
+                                        .as_ref()
+                                        .and_then(|x| x.file_stem())
+                                        .map(|x| x.to_string_lossy().to_string())
+                                        .unwrap_or_else(|| "Game default".to_owned()),
+                                )
                                 .size(25)
-                                .width(Length::FillPortion(1)),
-                        ),
+                                .width(Length::FillPortion(3)),
+                            )
+                            .push(
+                                Button::new(&mut self.worlds_button, Text::new("Choose"))
+                                    .width(Length::FillPortion(1))
+                                    .on_press(Message::OpenWorlds)
+                                    .style(self.theme),
+                            ),
                     )
                     .push(Space::new(Length::Fill, Length::Fill))
+                    .push(Text::new(&self.launch_status))
                     .push(
                         Row::new()
                             .height(Length::Shrink)
@@ -261,6 +1180,131 @@ impl Application for App {
                                 .style(self.theme),
                             ),
                     )
+                    .push(
+                        Row::new()
+                            .spacing(20)
+                            .push(
+                                Text::new("Save Folder")
+                                    .size(25)
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .push(
+                                TextInput::new(
+                                    &mut self.save_path_text,
+                                    "The game's saves folder",
+                                    &self
+                                        .config
+                                        .save_path
+                                        .as_ref()
+                                        .map(|x| x.to_string_lossy().to_string())
+                                        .unwrap_or_default(),
+                                    |x| Message::SettingsUpdate(config::ConfigUpdate::SavePath(x)),
+                                )
+                                .width(Length::FillPortion(4))
+                                .style(self.theme),
+                            ),
+                    )
+                    .push(
+                        Row::new()
+                            .spacing(20)
+                            .push(
+                                Text::new("Keep Deleted")
+                                    .size(25)
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .push(
+                                Slider::new(
+                                    &mut self.trash_days_slider,
+                                    0.0..=90.0,
+                                    self.config.trash_days as f64,
+                                    |x| {
+                                        Message::SettingsUpdate(config::ConfigUpdate::TrashDays(
+                                            x as u32,
+                                        ))
+                                    },
+                                )
+                                .width(Length::FillPortion(3))
+                                .style(self.theme),
+                            )
+                            .push(
+                                Text::new(match self.config.trash_days {
+                                    0 => "[ Forever ]".to_owned(),
+                                    i => format!("[ {:0>2} days ]", i),
+                                })
+                                .width(Length::FillPortion(1)),
+                            ),
+                    )
+                    .push(
+                        Row::new()
+                            .spacing(20)
+                            .push(
+                                Text::new("World Backups")
+                                    .size(25)
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .push(
+                                Slider::new(
+                                    &mut self.backup_count_slider,
+                                    0.0..=30.0,
+                                    self.config.backup_count as f64,
+                                    |x| {
+                                        Message::SettingsUpdate(config::ConfigUpdate::BackupCount(
+                                            x as u32,
+                                        ))
+                                    },
+                                )
+                                .width(Length::FillPortion(3))
+                                .style(self.theme),
+                            )
+                            .push(
+                                Text::new(match self.config.backup_count {
+                                    0 => "[ Off ]".to_owned(),
+                                    i => format!("[ {:0>2} kept ]", i),
+                                })
+                                .width(Length::FillPortion(1)),
+                            ),
+                    )
+                    .push(
+                        Row::new()
+                            .spacing(20)
+                            .push(
+                                Text::new("Resource Packs")
+                                    .size(25)
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .push(
+                                Checkbox::new(
+                                    self.config.fix_images,
+                                    "Resize images that don't match the game's",
+                                    |x| Message::SettingsUpdate(config::ConfigUpdate::FixImages(x)),
+                                )
+                                .width(Length::FillPortion(4))
+                                .style(self.theme),
+                            ),
+                    )
+                    .push(
+                        Row::new()
+                            .spacing(20)
+                            .push(
+                                Text::new("Default Pack Compression")
+                                    .size(25)
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .push(
+                                PickList::new(
+                                    &mut self.compression_pick_list,
+                                    &Compression::ALL[..],
+                                    Some(self.config.default_compression),
+                                    |x| {
+                                        Message::SettingsUpdate(
+                                            config::ConfigUpdate::DefaultCompression(x),
+                                        )
+                                    },
+                                )
+                                .width(Length::FillPortion(4))
+                                .style(self.theme),
+                            ),
+                    )
                     .push(Space::new(Length::Fill, Length::Fill))
                     .push(
                         Row::new()
@@ -286,6 +1330,1044 @@ impl Application for App {
             .height(Length::Fill)
             .style(self.theme)
             .into(),
+
+            View::ResourcePacks => {
+                let theme = self.theme;
+                let enabled = &self.config.resource_packs;
+                let mut list = Scrollable::new(&mut self.pack_list)
+                    .spacing(5)
+                    .height(Length::Fill)
+                    .style(theme);
+
+                for (i, pack) in self.packs.iter_mut().enumerate() {
+                    let priority = enabled.iter().position(|x| *x == pack.file);
+                    let info = match &pack.error {
+                        None => format!("{} by {} (v{})", pack.name, pack.author, pack.version),
+                        Some(e) => format!("{} (Failed to load: {})", pack.file, e),
+                    };
+                    let mut info = Column::new()
+                        .width(Length::Fill)
+                        .spacing(5)
+                        .push(Text::new(info).size(20));
+                    if let Some(i) = &pack.description {
+                        info = info.push(Text::new(i).size(16));
+                    }
+                    if !pack.requires.is_empty() {
+                        let needs = pack.requires.iter().map(|x| x.to_string());
+                        info = info.push(
+                            Text::new(format!("Needs {}", needs.collect::<Vec<_>>().join(", ")))
+                                .size(16),
+                        );
+                    }
+                    if let Some(i) = &pack.signature {
+                        info = info.push(Text::new(i.to_string()).size(16));
+                    }
+                    for i in &pack.image_mismatches {
+                        info = info.push(Text::new(i).size(16));
+                    }
+                    if !pack.previews.is_empty() {
+                        info = info.push(pack.previews.iter().fold(
+                            Row::new().spacing(5),
+                            |row, x| {
+                                row.push(
+                                    Image::new(x.clone())
+                                        .height(Length::Units(PREVIEW_HEIGHT as u16)),
+                                )
+                            },
+                        ));
+                    }
+                    let thumbnail: Element<Message> = match &pack.thumbnail {
+                        Some(i) => Image::new(i.clone())
+                            .width(Length::Units(THUMBNAIL_SIZE as u16))
+                            .height(Length::Units(THUMBNAIL_SIZE as u16))
+                            .into(),
+                        None => Space::with_width(Length::Units(THUMBNAIL_SIZE as u16)).into(),
+                    };
+
+                    let mut row = Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(
+                            Checkbox::new(priority.is_some(), "", move |x| {
+                                Message::PackToggle(i, x)
+                            })
+                            .style(theme),
+                        )
+                        .push(thumbnail)
+                        .push(info)
+                        .push(Text::new(match priority {
+                            Some(i) => format!("#{}", i + 1),
+                            None => String::new(),
+                        }))
+                        .push(
+                            Button::new(&mut pack.raise_button, Text::new("Raise"))
+                                .on_press(Message::PackRaise(i))
+                                .style(theme),
+                        )
+                        .push(
+                            Button::new(&mut pack.diff_button, Text::new("Changes"))
+                                .on_press(Message::PackDiff(i))
+                                .style(theme),
+                        )
+                        .push(
+                            Button::new(&mut pack.verify_button, Text::new("Verify"))
+                                .on_press(Message::PackVerify(i))
+                                .style(theme),
+                        );
+                    match pack.signature {
+                        Some(SignatureStatus::Unknown(_)) => {
+                            row = row.push(
+                                Button::new(&mut pack.trust_button, Text::new("Trust"))
+                                    .on_press(Message::PackTrust(i))
+                                    .style(theme),
+                            );
+                        }
+                        Some(SignatureStatus::Trusted(_)) => {
+                            row = row.push(
+                                Button::new(&mut pack.trust_button, Text::new("Untrust"))
+                                    .on_press(Message::PackUntrust(i))
+                                    .style(theme),
+                            );
+                        }
+                        _ => {}
+                    }
+                    if pack.folder {
+                        row = row.push(
+                            Button::new(&mut pack.sign_button, Text::new("Sign"))
+                                .on_press(Message::PackSign(i))
+                                .style(theme),
+                        );
+                    }
+                    let confirm = self.pack_delete.as_ref() == Some(&pack.file);
+                    list = list.push(
+                        row.push(
+                            Button::new(
+                                &mut pack.delete_button,
+                                Text::new(if confirm { "Confirm delete" } else { "Delete" }),
+                            )
+                            .on_press(Message::PackDelete(i, confirm))
+                            .style(theme),
+                        ),
+                    );
+                }
+
+                Container::new(
+                    Column::new()
+                        .padding(20)
+                        .spacing(10)
+                        .push(Text::new("Resource Packs").size(40))
+                        .push(list)
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .push(
+                                    TextInput::new(
+                                        &mut self.pack_import_text,
+                                        "Path to a .zip, .tar, .tar.gz, .tar.xz, .tar.zst or pack folder",
+                                        &self.pack_import_path,
+                                        Message::PackImportPath,
+                                    )
+                                    .padding(5)
+                                    .width(Length::FillPortion(4))
+                                    .style(theme),
+                                )
+                                .push(
+                                    Button::new(&mut self.pack_import_button, Text::new("Import"))
+                                        .width(Length::FillPortion(1))
+                                        .on_press(Message::PackImport)
+                                        .style(theme),
+                                ),
+                        )
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .push(
+                                    TextInput::new(
+                                        &mut self.pack_create_name_text,
+                                        "New pack name",
+                                        &self.pack_create_name,
+                                        Message::PackCreateName,
+                                    )
+                                    .padding(5)
+                                    .width(Length::FillPortion(2))
+                                    .style(theme),
+                                )
+                                .push(
+                                    TextInput::new(
+                                        &mut self.pack_create_author_text,
+                                        "Author",
+                                        &self.pack_create_author,
+                                        Message::PackCreateAuthor,
+                                    )
+                                    .padding(5)
+                                    .width(Length::FillPortion(2))
+                                    .style(theme),
+                                )
+                                .push(
+                                    Checkbox::new(
+                                        self.pack_create_sign,
+                                        "Sign",
+                                        Message::PackCreateSign,
+                                    )
+                                    .width(Length::Shrink)
+                                    .style(theme),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.pack_create_button,
+                                        Text::new("Create from data"),
+                                    )
+                                    .width(Length::FillPortion(1))
+                                    .on_press(Message::PackCreate)
+                                    .style(theme),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.pack_merge_button,
+                                        Text::new("Merge enabled"),
+                                    )
+                                    .width(Length::FillPortion(1))
+                                    .on_press(Message::PackMerge)
+                                    .style(theme),
+                                ),
+                        )
+                        .push(Text::new(&self.pack_status))
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .push(
+                                    Button::new(
+                                        &mut self.packs_exit_button,
+                                        Text::new("Back").size(25),
+                                    )
+                                    .on_press(Message::PacksExit)
+                                    .style(theme),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.packs_apply_button,
+                                        Text::new("Apply").size(25),
+                                    )
+                                    .on_press(Message::PacksApply)
+                                    .style(theme),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.packs_reset_button,
+                                        Text::new("Reset to original resources").size(25),
+                                    )
+                                    .on_press(Message::PacksReset)
+                                    .style(theme),
+                                ),
+                        ),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(theme)
+                .into()
+            }
+
+            View::PackDiff => {
+                let theme = self.theme;
+                let (file, diff) = self.pack_diff.as_ref().unwrap();
+                let mut list = Scrollable::new(&mut self.pack_diff_list)
+                    .spacing(5)
+                    .height(Length::Fill)
+                    .style(theme);
+
+                list =
+                    list.push(Text::new(format!("Overrides ({})", diff.overrides.len())).size(25));
+                for i in &diff.overrides {
+                    let text = match (i.original_size, i.new_size) {
+                        (Some(a), Some(b)) if i.resized() => {
+                            format!("{} ({}x{} -> {}x{})", i.asset, a.0, a.1, b.0, b.1)
+                        }
+                        _ => i.asset.to_owned(),
+                    };
+                    list = list.push(Text::new(text).size(16));
+                }
+
+                list =
+                    list.push(Text::new(format!("Additions ({})", diff.additions.len())).size(25));
+                for i in &diff.additions {
+                    list = list.push(Text::new(i).size(16));
+                }
+
+                list =
+                    list.push(Text::new(format!("Untouched ({})", diff.untouched.len())).size(25));
+                for i in &diff.untouched {
+                    list = list.push(Text::new(i).size(16));
+                }
+
+                Container::new(
+                    Column::new()
+                        .padding(20)
+                        .spacing(10)
+                        .push(Text::new(format!("Changes made by {}", file)).size(40))
+                        .push(list)
+                        .push(Text::new(&self.pack_diff_status))
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .push(
+                                    Button::new(
+                                        &mut self.pack_diff_exit_button,
+                                        Text::new("Back").size(25),
+                                    )
+                                    .on_press(Message::PackDiffExit)
+                                    .style(theme),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.pack_diff_export_button,
+                                        Text::new("Export JSON").size(25),
+                                    )
+                                    .on_press(Message::PackDiffExport)
+                                    .style(theme),
+                                ),
+                        ),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(theme)
+                .into()
+            }
+
+            View::Worlds => {
+                let theme = self.theme;
+                let templates = template_choices(&self.worlds);
+                let dirs = self
+                    .save_dirs()
+                    .iter()
+                    .map(|x| x.to_string_lossy().to_string())
+                    .collect::<Vec<_>>();
+                let mut list = Scrollable::new(&mut self.world_list)
+                    .spacing(5)
+                    .height(Length::Fill)
+                    .style(theme);
+
+                // The game's own save, without copying a world over it
+                let active = self.config.world.is_none();
+                let mut select = Button::new(
+                    &mut self.world_default_button,
+                    Text::new(if active { "Active" } else { "Select" }),
+                )
+                .style(theme);
+                if !active {
+                    select = select.on_press(Message::WorldSelectDefault);
+                }
+                list = list.push(
+                    Row::new()
+                        .spacing(10)
+                        .align_items(Align::Center)
+                        .push(Text::new("Game default").width(Length::Fill))
+                        .push(select),
+                );
+
+                if self.worlds.is_empty() {
+                    list = list.push(Text::new(format!("No worlds found in {}", dirs.join(", "))));
+                }
+
+                let mut import_row = Row::new()
+                    .spacing(10)
+                    .push(
+                        TextInput::new(
+                            &mut self.world_import_text,
+                            "Path to an exported world (.world.tar.gz)",
+                            &self.world_import_path,
+                            Message::WorldImportPath,
+                        )
+                        .padding(5)
+                        .width(Length::FillPortion(4))
+                        .style(theme),
+                    )
+                    .push(
+                        Button::new(&mut self.world_import_button, Text::new("Import"))
+                            .width(Length::FillPortion(1))
+                            .on_press(Message::WorldImport(false))
+                            .style(theme),
+                    );
+                if self.world_import_exists {
+                    import_row = import_row.push(
+                        Button::new(&mut self.world_replace_button, Text::new("Replace"))
+                            .on_press(Message::WorldImport(true))
+                            .style(theme),
+                    );
+                }
+
+                for (i, entry) in self.worlds.iter_mut().enumerate() {
+                    let active = self.config.world.as_ref() == Some(&entry.world.path);
+                    let mut select = Button::new(
+                        &mut entry.select_button,
+                        Text::new(if active { "Active" } else { "Select" }),
+                    )
+                    .style(theme);
+                    if !active {
+                        select = select.on_press(Message::WorldSelect(i));
+                    }
+
+                    list = list.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Align::Center)
+                            .push(Text::new(&entry.world.name).width(Length::FillPortion(3)))
+                            .push(
+                                Text::new(entry.world.size_text())
+                                    .size(16)
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .push(
+                                Text::new(entry.world.modified_text())
+                                    .size(16)
+                                    .width(Length::FillPortion(2)),
+                            )
+                            .push(select)
+                            .push(
+                                Button::new(&mut entry.export_button, Text::new("Export"))
+                                    .on_press(Message::WorldExport(i))
+                                    .style(theme),
+                            )
+                            .push(
+                                Button::new(&mut entry.delete_button, Text::new("Delete"))
+                                    .on_press(Message::WorldDelete(i))
+                                    .style(theme),
+                            ),
+                    );
+                }
+
+                Container::new(
+                    Column::new()
+                        .padding(20)
+                        .spacing(10)
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .align_items(Align::Center)
+                                .push(Text::new("Worlds").size(40).width(Length::Fill))
+                                .push(
+                                    Button::new(
+                                        &mut self.worlds_backups_button,
+                                        Text::new("Backups"),
+                                    )
+                                    .on_press(Message::OpenBackups)
+                                    .style(theme),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.worlds_deleted_button,
+                                        Text::new("Deleted worlds"),
+                                    )
+                                    .on_press(Message::OpenDeletedWorlds)
+                                    .style(theme),
+                                ),
+                        )
+                        .push(list)
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .push(
+                                    TextInput::new(
+                                        &mut self.world_create_text,
+                                        "New world name",
+                                        &self.world_create_name,
+                                        Message::WorldCreateName,
+                                    )
+                                    .padding(5)
+                                    .width(Length::FillPortion(2))
+                                    .style(theme),
+                                )
+                                .push(
+                                    PickList::new(
+                                        &mut self.world_template_pick_list,
+                                        templates,
+                                        Some(self.world_template.clone()),
+                                        Message::WorldTemplate,
+                                    )
+                                    .width(Length::FillPortion(2))
+                                    .style(theme),
+                                )
+                                .push(
+                                    Button::new(&mut self.world_create_button, Text::new("Create"))
+                                        .on_press(Message::WorldCreate(false))
+                                        .style(theme),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.world_launch_button,
+                                        Text::new("Create and launch"),
+                                    )
+                                    .on_press(Message::WorldCreate(true))
+                                    .style(theme),
+                                ),
+                        )
+                        .push(import_row)
+                        .push(
+                            TextInput::new(
+                                &mut self.world_export_text,
+                                "Note to export worlds with (optional)",
+                                &self.world_export_note,
+                                Message::WorldExportNote,
+                            )
+                            .padding(5)
+                            .style(theme),
+                        )
+                        .push(Text::new(&self.world_status))
+                        .push(
+                            Button::new(&mut self.worlds_exit_button, Text::new("Back").size(25))
+                                .on_press(Message::WorldsExit)
+                                .style(theme),
+                        ),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(theme)
+                .into()
+            }
+
+            View::Backups => {
+                let theme = self.theme;
+                let mut list = Scrollable::new(&mut self.backup_list)
+                    .spacing(5)
+                    .height(Length::Fill)
+                    .style(theme);
+
+                if self.backups.is_empty() {
+                    list = list.push(Text::new("There are no backups yet"));
+                }
+
+                for (i, entry) in self.backups.iter_mut().enumerate() {
+                    list = list.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Align::Center)
+                            .push(Text::new(entry.backup.title()).width(Length::FillPortion(3)))
+                            .push(
+                                Text::new(worlds::time_text(entry.backup.time()))
+                                    .size(16)
+                                    .width(Length::FillPortion(2)),
+                            )
+                            .push(
+                                Text::new(worlds::size_text(entry.backup.size))
+                                    .size(16)
+                                    .width(Length::FillPortion(1)),
+                            )
+                            .push(
+                                Button::new(&mut entry.restore_button, Text::new("Restore"))
+                                    .on_press(Message::BackupRestore(i))
+                                    .style(theme),
+                            ),
+                    );
+                }
+
+                let info = match self.config.backup_count {
+                    0 => "Backups before launching are turned off".to_owned(),
+                    i => format!(
+                        "Worlds are backed up before launching, the newest {} of each are kept",
+                        i
+                    ),
+                };
+
+                Container::new(
+                    Column::new()
+                        .padding(20)
+                        .spacing(10)
+                        .push(Text::new("World Backups").size(40))
+                        .push(Text::new(info).size(16))
+                        .push(list)
+                        .push(Text::new(&self.world_status))
+                        .push(
+                            Button::new(&mut self.backups_exit_button, Text::new("Back").size(25))
+                                .on_press(Message::BackupsExit)
+                                .style(theme),
+                        ),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(theme)
+                .into()
+            }
+
+            View::DeletedWorlds => {
+                let theme = self.theme;
+                let mut list = Scrollable::new(&mut self.deleted_list)
+                    .spacing(5)
+                    .height(Length::Fill)
+                    .style(theme);
+
+                if self.deleted.is_empty() {
+                    list = list.push(Text::new("There are no deleted worlds"));
+                }
+
+                for (i, entry) in self.deleted.iter_mut().enumerate() {
+                    list = list.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_items(Align::Center)
+                            .push(
+                                Column::new()
+                                    .width(Length::FillPortion(3))
+                                    .push(Text::new(&entry.world.name))
+                                    .push(
+                                        Text::new(entry.world.original.to_string_lossy()).size(16),
+                                    ),
+                            )
+                            .push(
+                                Text::new(format!(
+                                    "Deleted {}",
+                                    worlds::time_text(entry.world.deleted_time())
+                                ))
+                                .size(16)
+                                .width(Length::FillPortion(2)),
+                            )
+                            .push(
+                                Button::new(&mut entry.restore_button, Text::new("Restore"))
+                                    .on_press(Message::DeletedRestore(i))
+                                    .style(theme),
+                            )
+                            .push(
+                                Button::new(&mut entry.purge_button, Text::new("Purge"))
+                                    .on_press(Message::DeletedPurge(i))
+                                    .style(theme),
+                            ),
+                    );
+                }
+
+                let purge = match self.config.trash_days {
+                    0 => "Deleted worlds are kept until they are purged".to_owned(),
+                    i => format!("Deleted worlds are purged after {} days", i),
+                };
+
+                Container::new(
+                    Column::new()
+                        .padding(20)
+                        .spacing(10)
+                        .push(Text::new("Deleted Worlds").size(40))
+                        .push(Text::new(purge).size(16))
+                        .push(list)
+                        .push(Text::new(&self.world_status))
+                        .push(
+                            Button::new(&mut self.deleted_exit_button, Text::new("Back").size(25))
+                                .on_press(Message::DeletedExit)
+                                .style(theme),
+                        ),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(theme)
+                .into()
+            }
+
+            View::StaleDefault => {
+                let changes = self.game_changes.as_ref().unwrap();
+
+                Container::new(
+                    Column::new()
+                        .padding(20)
+                        .spacing(10)
+                        .push(Text::new("The game has been updated").size(40))
+                        .push(Text::new(format!(
+                            "The game has changed since the original resources were saved ({}). \
+                            Restoring from the old copy would put back outdated files.",
+                            changes
+                        )))
+                        .push(Text::new(
+                            "Rebuild the copy from the game now? The old copy is kept in the snapshots folder.",
+                        ))
+                        .push(Text::new(&self.default_status))
+                        .push(Space::new(Length::Fill, Length::Fill))
+                        .push(
+                            Row::new()
+                                .spacing(10)
+                                .push(
+                                    Button::new(
+                                        &mut self.default_rebuild_button,
+                                        Text::new("Rebuild").size(25),
+                                    )
+                                    .on_press(Message::DefaultRebuild)
+                                    .style(self.theme),
+                                )
+                                .push(
+                                    Button::new(
+                                        &mut self.default_keep_button,
+                                        Text::new("Not now").size(25),
+                                    )
+                                    .on_press(Message::DefaultKeep)
+                                    .style(self.theme),
+                                ),
+                        ),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(self.theme)
+                .into()
+            }
+        }
+    }
+}
+
+impl App {
+    /// Reload the list of packs from the resource_packs folder.
+    /// Images are loaded by the returned command, until then packs keep the images they had.
+    fn refresh_packs(&mut self) -> Command<Message> {
+        let trust = TrustStore::load(&data_path());
+        let old = std::mem::take(&mut self.packs);
+        self.packs = resource_pack::find_packs(&data_path().join("resource_packs"))
+            .into_iter()
+            .filter(|x| x.file_name().unwrap() != "default.tar")
+            .map(|x| {
+                let file = x.file_name().unwrap().to_string_lossy().to_string();
+                let kept = old.iter().find(|x| x.file == file);
+                match ResourcePack::load(x) {
+                    Ok(i) => PackEntry {
+                        thumbnail: kept.and_then(|x| x.thumbnail.clone()),
+                        previews: kept.map(|x| x.previews.clone()).unwrap_or_default(),
+                        image_mismatches: kept
+                            .map(|x| x.image_mismatches.clone())
+                            .unwrap_or_default(),
+                        file,
+                        signature: Some(i.signature_status(&trust)),
+                        requires: i.requires.clone(),
+                        conflicts: i.conflicts.clone(),
+                        folder: i.format == PackFormat::Dir,
+                        name: i.name,
+                        author: i.author,
+                        version: i.version,
+                        description: i.description,
+                        ..Default::default()
+                    },
+                    Err(e) => PackEntry {
+                        file,
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                }
+            })
+            .collect();
+
+        let files = self
+            .packs
+            .iter()
+            .filter(|x| x.error.is_none())
+            .map(|x| x.file.to_owned())
+            .collect();
+        let cached = self.pack_images.keys().cloned().collect();
+        Command::perform(load_pack_images(files, cached), Message::PackImages)
+    }
+
+    /// Update the game's config, install the enabled packs and load the active world,
+    /// then run the game. Progress is copied back into the world when the game closes.
+    /// The game isn't run if the packs can't be applied or the world can't be loaded.
+    fn launch(&self) -> Result<(), String> {
+        println!(
+            "[*] Updateing Game Config (FullScreen: {}, Volume: {})",
+            self.config.full_screen, self.config.volume
+        );
+
+        fs::write(
+            self.config.game_path.join("freeways.cfg"),
+            format!(
+                r#"<Freeways fullScreen="{}" screenWidth="0" useFBO="false" volume="{}"/>{}"#,
+                self.config.full_screen, self.config.volume, "\n"
+            ),
+        )
+        .unwrap();
+
+        if let Err(e) = self.apply_packs() {
+            println!("[!] {}", e);
+            return Err(format!(
+                "Didn't launch, the resource packs couldn't be applied: {}",
+                e
+            ));
+        }
+
+        if self.config.backup_count > 0 {
+            self.backup_worlds();
+        }
+
+        if let Some(i) = &self.config.world {
+            print!("[*] Loading World ({}) ", i.to_string_lossy());
+            let backups = data_path().join(worlds::ACTIVE_BACKUP_FOLDER);
+            match worlds::activate(i, &self.active_save(), &backups) {
+                Ok(_) => println!("[✅]"),
+                Err(e) => {
+                    println!("[❌]");
+                    return Err(format!(
+                        "Didn't launch, the world couldn't be loaded: {}",
+                        e
+                    ));
+                }
+            }
+        }
+
+        let path = self.config.game_path.join("Freeways.exe");
+        println!("[*] Launching Game ({})", path.to_string_lossy());
+        process::Command::new(path).output().unwrap();
+
+        if let Some(i) = &self.config.world {
+            print!("[*] Saving World ({}) ", i.to_string_lossy());
+            match worlds::store(i, &self.active_save()) {
+                Some(_) => println!("[✅]"),
+                None => println!("[❌]"),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reload the list of worlds from the save folders
+    fn refresh_worlds(&mut self) {
+        self.worlds = worlds::find_worlds(&self.save_dirs())
+            .into_iter()
+            .map(|world| WorldEntry {
+                world,
+                select_button: button::State::new(),
+                export_button: button::State::new(),
+                delete_button: button::State::new(),
+            })
+            .collect();
+    }
+
+    /// Reload the list of world backups
+    fn refresh_backups(&mut self) {
+        self.backups = backup::list_backups(&data_path())
+            .into_iter()
+            .map(|backup| BackupEntry {
+                backup,
+                restore_button: button::State::new(),
+            })
+            .collect();
+    }
+
+    /// Back up the active world, or every save folder if there isn't one, with the game's active
+    /// save, then thin out the old backups
+    fn backup_worlds(&self) {
+        let (label, mut files) = match &self.config.world {
+            Some(i) => (
+                backup::world_label(
+                    &i.file_stem()
+                        .map(|x| x.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                ),
+                vec![i.to_owned()],
+            ),
+            None => (
+                backup::ALL_SAVES_LABEL.to_owned(),
+                worlds::save_files(&self.save_dirs()),
+            ),
+        };
+
+        // The game's active save can hold progress that isn't in any world yet
+        let active = self.active_save();
+        if !files.contains(&active) {
+            files.push(active);
+        }
+
+        print!("[*] Backing Up Worlds ({}) ", label);
+        match backup::create_backup(&data_path(), &label, &files) {
+            Ok(_) => println!("[✅]"),
+            Err(e) => {
+                println!("[❌]");
+                println!("[!] {}", e);
+            }
+        }
+
+        let removed = backup::rotate(&data_path(), self.config.backup_count as usize);
+        if removed > 0 {
+            println!("[*] Removed {} old backup(s)", removed);
+        }
+    }
+
+    /// Reload the list of worlds in the deleted folder
+    fn refresh_deleted(&mut self) {
+        self.deleted = trash::deleted_worlds(&data_path())
+            .into_iter()
+            .map(|world| DeletedEntry {
+                world,
+                restore_button: button::State::new(),
+                purge_button: button::State::new(),
+            })
+            .collect();
+    }
+
+    /// Names of the enabled packs, for recording what a world was played with
+    fn pack_names(&self) -> Vec<String> {
+        self.config
+            .resource_packs
+            .iter()
+            .map(|file| match self.packs.iter().find(|x| x.file == *file) {
+                Some(i) if i.error.is_none() => i.name.to_owned(),
+                _ => file.to_owned(),
+            })
+            .collect()
+    }
+
+    /// What the packs that loaded need from each other, for enabling them
+    fn pack_deps(&self) -> Vec<PackDeps> {
+        self.packs
+            .iter()
+            .filter(|x| x.error.is_none())
+            .map(|x| PackDeps {
+                file: x.file.to_owned(),
+                name: x.name.to_owned(),
+                version: x.version.to_owned(),
+                requires: x.requires.clone(),
+                conflicts: x.conflicts.clone(),
+            })
+            .collect()
+    }
+
+    fn save_dirs(&self) -> Vec<PathBuf> {
+        worlds::save_dirs(&self.config.game_path, self.config.save_path.as_deref())
+    }
+
+    fn active_save(&self) -> PathBuf {
+        worlds::active_save(&self.config.game_path, self.config.save_path.as_deref())
+    }
+
+    /// Install the enabled packs and uninstall any that were disabled
+    /// Returns the hand edited files that were left alone, and any images that don't match the game's.
+    fn apply_packs(&self) -> Result<(Vec<String>, Vec<String>), String> {
+        // A pack left out here would be uninstalled, so every enabled pack has to load
+        for i in &self.config.resource_packs {
+            match self.packs.iter().find(|x| x.file == *i) {
+                Some(PackEntry { error: None, .. }) => {}
+                Some(PackEntry { error: Some(e), .. }) => {
+                    return Err(format!("Failed to load resource pack '{}': {}", i, e))
+                }
+                None => return Err(format!("Resource pack '{}' is missing", i)),
+            }
+        }
+
+        let path = data_path().join("resource_packs");
+        let packs = resolve_dependencies(&self.pack_deps(), &self.config.resource_packs)?;
+        let mut stack = PackStack::load(&path, &packs)?;
+        let images = stack.check_images(&data_path(), self.config.fix_images);
+
+        print!("[*] Applying Resource Packs ({}) ", packs.join(", "));
+
+        let edited = stack.install(&data_path(), &self.config.game_path);
+        match edited {
+            Some(_) => println!("[✅]"),
+            None => println!("[❌]"),
+        }
+        edited
+            .map(|x| (x, images))
+            .ok_or_else(|| "no changes were made".to_owned())
+    }
+
+    fn save_config(&self) {
+        println!(
+            "[*] Saveing Config (ResourcePacks: '{}')",
+            self.config.resource_packs.join(", ")
+        );
+        self.config.save(data_path().join("config.cfg"));
+    }
+}
+
+fn data_path() -> PathBuf {
+    home_dir().unwrap().join(Path::new(CFG_PATH))
+}
+
+fn pack_choices(packs: &[PackEntry]) -> Vec<PackChoice> {
+    let mut out = vec![PackChoice::None];
+    out.extend(
+        packs
+            .iter()
+            .filter(|x| x.error.is_none())
+            .map(|x| PackChoice::Pack(x.file.to_owned())),
+    );
+    out
+}
+
+fn template_choices(worlds: &[WorldEntry]) -> Vec<TemplateChoice> {
+    let mut out = vec![TemplateChoice::None];
+    out.extend(
+        worlds
+            .iter()
+            .map(|x| TemplateChoice::World(x.world.path.to_owned())),
+    );
+    out
+}
+
+/// Describe an imported world and what it was exported with
+fn import_text(world: &World, info: &WorldInfo) -> String {
+    let mut out = format!("Imported {}", world.name);
+    if info.launcher_version != VERSION {
+        out.push_str(&format!(
+            ", exported with launcher {}",
+            info.launcher_version
+        ));
+    }
+    if !info.resource_packs.is_empty() {
+        out.push_str(&format!(", played with {}", info.resource_packs.join(", ")));
+    }
+    if let Some(i) = &info.note {
+        out.push_str(&format!("\nNote: {}", i));
+    }
+
+    out
+}
+
+/// Decode the thumbnail and previews of each pack and check its images against the game's,
+/// skipping packs whose hash is in `cached`.
+/// Run as a command, as every pack has to be read in full.
+async fn load_pack_images(
+    files: Vec<String>,
+    cached: BTreeSet<String>,
+) -> Vec<(String, String, Option<PackImages>)> {
+    let path = data_path().join("resource_packs");
+    let default = resource_pack::load_default(&data_path()).ok();
+    files
+        .into_iter()
+        .filter_map(|file| {
+            let pack = ResourcePack::load(path.join(&file)).ok()?;
+            let hash = ledger::pack_hash(&pack)?;
+            let images = (!cached.contains(&hash)).then(|| PackImages {
+                thumbnail: pack.thumbnail().map(image_handle),
+                previews: pack
+                    .preview_images()
+                    .into_iter()
+                    .map(image_handle)
+                    .collect(),
+                mismatches: match &default {
+                    Some(i) => match pack.check_images(i) {
+                        Some(i) => i.iter().map(|x| x.to_string()).collect(),
+                        None => vec!["Failed to check the images".to_owned()],
+                    },
+                    None => Vec::new(),
+                },
+            });
+            Some((file, hash, images))
+        })
+        .collect()
+}
+
+/// Convert a decoded image into the BGRA pixels iced wants
+fn image_handle(image: RgbaImage) -> Handle {
+    let (width, height) = image.dimensions();
+    let mut pixels = image.into_raw();
+    pixels.chunks_exact_mut(4).for_each(|x| x.swap(0, 2));
+
+    Handle::from_pixels(width, height, pixels)
+}
+
+impl fmt::Display for TemplateChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateChoice::None => f.write_str("Empty world"),
+            TemplateChoice::World(i) => match i.file_stem() {
+                Some(i) => write!(f, "Copy of {}", i.to_string_lossy()),
+                None => f.write_str("Copy of a world"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for PackChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackChoice::None => f.write_str("None"),
+            PackChoice::Pack(i) => f.write_str(i),
         }
     }
 }
@@ -0,0 +1,351 @@
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::install::Transaction;
+use crate::resource_pack::{self, Compression};
+use crate::worlds::in_save_dirs;
+
+/// Folder in the launcher's data folder that world backups are kept in
+pub const BACKUP_FOLDER: &str = "world_backups";
+/// First file in every backup, saying where each file was backed up from
+const INFO_FILE: &str = "backup.json";
+const BACKUP_EXT: &str = ".tar.gz";
+/// Label of backups of every save folder
+pub const ALL_SAVES_LABEL: &str = "saves";
+/// Start of the label of a backup of one world, so no world's backups are mixed up with
+/// [`ALL_SAVES_LABEL`]
+const WORLD_PREFIX: &str = "world-";
+
+/// Days to keep one backup a day for, once it is older than the newest few
+const DAILY_DAYS: u64 = 7;
+/// Weeks to keep one backup a week for, after the daily ones
+const WEEKLY_WEEKS: u64 = 8;
+const DAY: u64 = 24 * 60 * 60;
+
+/// A backup archive, named `<label>-<unix time>.tar.gz`
+#[derive(Debug, Clone)]
+pub struct Backup {
+    /// `world-<name>` for a world, or [`ALL_SAVES_LABEL`] for every save folder
+    pub label: String,
+    /// Unix time the backup was made
+    pub time: u64,
+    pub size: u64,
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BackupInfo {
+    /// Name of each file in the archive, with where it was backed up from
+    files: Vec<(String, PathBuf)>,
+}
+
+/// Archive files into a new backup.
+/// Files that don't exist are skipped, there must be at least one that does.
+pub fn create_backup(path: &Path, label: &str, files: &[PathBuf]) -> Result<Backup, String> {
+    let dir = path.join(BACKUP_FOLDER);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to make the backup folder: {}", e))?;
+
+    let files = files.iter().filter(|x| x.is_file()).collect::<Vec<_>>();
+    if files.is_empty() {
+        return Err("There is nothing to back up".to_owned());
+    }
+
+    let mut time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs();
+    while dir.join(backup_name(label, time)).exists() {
+        time += 1;
+    }
+    let to = dir.join(backup_name(label, time));
+
+    let info = BackupInfo {
+        files: files
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (format!("files/{}", i), x.to_path_buf()))
+            .collect(),
+    };
+    if write_backup(&to, &info).is_none() {
+        fs::remove_file(&to).unwrap_or_default();
+        return Err(format!("Failed to write {}", to.to_string_lossy()));
+    }
+
+    Backup::load(to).ok_or_else(|| "Failed to read the new backup".to_owned())
+}
+
+fn write_backup(to: &Path, info: &BackupInfo) -> Option<()> {
+    let file = File::create(to).ok()?;
+    let mut a = tar::Builder::new(Compression::Gzip.encoder(file).ok()?);
+
+    resource_pack::append_file(&mut a, INFO_FILE, &serde_json::to_vec_pretty(info).ok()?)?;
+    for (name, from) in &info.files {
+        a.append_path_with_name(from, name).ok()?;
+    }
+    a.into_inner().ok()?.finish().ok()
+}
+
+/// Get every backup, newest first
+pub fn list_backups(path: &Path) -> Vec<Backup> {
+    let mut out = match fs::read_dir(path.join(BACKUP_FOLDER)) {
+        Ok(i) => i
+            .filter_map(|x| Backup::load(x.ok()?.path()))
+            .collect::<Vec<_>>(),
+        Err(_) => return Vec::new(),
+    };
+
+    out.sort_by_key(|x| Reverse(x.time));
+    out
+}
+
+/// Remove old backups, keeping the newest `keep` of each label.
+/// Older backups are thinned to one a day for a week, then one a week for a couple of months.
+/// Returns how many were removed.
+pub fn rotate(path: &Path, keep: usize) -> usize {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    rotate_at(path, keep, now)
+}
+
+/// `rotate` as if it were `now`
+fn rotate_at(path: &Path, keep: usize, now: u64) -> usize {
+    let backups = list_backups(path);
+    let labels = backups.iter().map(|x| &x.label).collect::<BTreeSet<_>>();
+
+    let mut removed = 0;
+    for label in labels {
+        let group = backups
+            .iter()
+            .filter(|x| x.label == *label)
+            .collect::<Vec<_>>();
+        let times = group.iter().map(|x| x.time).collect::<Vec<_>>();
+        for (backup, keep) in group.iter().zip(thin(&times, now, keep)) {
+            if !keep && fs::remove_file(&backup.path).is_ok() {
+                removed += 1;
+            }
+        }
+    }
+
+    removed
+}
+
+/// Pick which backups to keep, given their unix times newest first.
+/// Days and weeks are counted in UTC.
+pub fn thin(times: &[u64], now: u64, keep: usize) -> Vec<bool> {
+    let mut days = BTreeSet::new();
+    let mut weeks = BTreeSet::new();
+
+    times
+        .iter()
+        .enumerate()
+        .map(|(i, time)| {
+            let (day, week) = (time / DAY, time / (7 * DAY));
+            let age = now.saturating_sub(*time) / DAY;
+
+            let out = if i < keep {
+                true
+            } else if age < DAILY_DAYS {
+                !days.contains(&day)
+            } else if age < WEEKLY_WEEKS * 7 {
+                !weeks.contains(&week)
+            } else {
+                false
+            };
+
+            if out {
+                days.insert(day);
+                weeks.insert(week);
+            }
+            out
+        })
+        .collect()
+}
+
+impl Backup {
+    pub fn load(path: PathBuf) -> Option<Backup> {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        let (label, time) = name.strip_suffix(BACKUP_EXT)?.rsplit_once('-')?;
+
+        Some(Backup {
+            label: label.to_owned(),
+            time: time.parse().ok()?,
+            size: fs::metadata(&path).ok()?.len(),
+            path,
+        })
+    }
+
+    pub fn time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.time)
+    }
+
+    /// What was backed up, the world's name or `All saves`
+    pub fn title(&self) -> &str {
+        match self.label.strip_prefix(WORLD_PREFIX) {
+            Some(i) => i,
+            None if self.label == ALL_SAVES_LABEL => "All saves",
+            None => &self.label,
+        }
+    }
+
+    /// Put every file in the backup back where it came from.
+    /// Nothing is restored if any file came from outside `save_dirs`.
+    /// The files being replaced are backed up first, so a restore can be undone.
+    /// If any file can't be written, the files restored so far are put back as they were.
+    /// Returns how many files were restored.
+    pub fn restore(&self, path: &Path, save_dirs: &[PathBuf]) -> Result<usize, String> {
+        let file = File::open(&self.path).map_err(|e| e.to_string())?;
+        let reader = Compression::Gzip.decoder(file).map_err(|e| e.to_string())?;
+        let mut archive = tar::Archive::new(reader);
+        let mut entries = archive.entries().map_err(|e| e.to_string())?;
+
+        let info = match entries.next() {
+            Some(Ok(mut i)) if i.path().ok().as_deref() == Some(Path::new(INFO_FILE)) => {
+                let mut data = Vec::new();
+                i.read_to_end(&mut data).map_err(|e| e.to_string())?;
+                serde_json::from_slice::<BackupInfo>(&data).map_err(|e| e.to_string())?
+            }
+            _ => {
+                return Err(format!(
+                    "{} is missing {}",
+                    self.path.to_string_lossy(),
+                    INFO_FILE
+                ))
+            }
+        };
+
+        if let Some(i) = info.files.iter().find(|x| !in_save_dirs(&x.1, save_dirs)) {
+            return Err(format!("{} isn't in a save folder", i.1.to_string_lossy()));
+        }
+
+        let current = info
+            .files
+            .iter()
+            .map(|x| x.1.to_owned())
+            .collect::<Vec<_>>();
+        if current.iter().any(|x| x.exists()) {
+            create_backup(path, &self.label, &current)?;
+        }
+
+        let mut transaction =
+            Transaction::new(path.join("backup")).ok_or("Failed to start restoring the backup")?;
+        let mut restored = 0;
+        for i in entries {
+            let written = i.ok().and_then(|mut i| {
+                let name = i.path().ok()?.to_string_lossy().to_string();
+                let to = &info.files.iter().find(|x| x.0 == name)?.1;
+                transaction.write_from(to, &mut i)
+            });
+
+            if written.is_none() {
+                transaction.rollback();
+                return Err("Failed to restore every file, nothing was changed".to_owned());
+            }
+            restored += 1;
+        }
+
+        transaction.commit();
+        Ok(restored)
+    }
+}
+
+/// Label for backups of the world named `name`
+pub fn world_label(name: &str) -> String {
+    format!("{}{}", WORLD_PREFIX, name)
+}
+
+fn backup_name(label: &str, time: u64) -> String {
+    format!("{}-{}{}", label, time, BACKUP_EXT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    const HOUR: u64 = 60 * 60;
+    /// Midday at the start of a week, weeks start on a Thursday as the unix epoch was one
+    const NOW: u64 = 300 * 7 * DAY + 12 * HOUR;
+
+    #[test]
+    fn keeps_newest() {
+        let times = [NOW, NOW - 1, NOW - 2, NOW - 3];
+        assert_eq!(thin(&times, NOW, 2), vec![true, true, false, false]);
+        assert_eq!(thin(&times, NOW, 4), vec![true; 4]);
+        assert_eq!(thin(&times, NOW, 0), vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn day_boundary() {
+        let midnight = NOW / DAY * DAY - 2 * DAY;
+        let times = [midnight + 1, midnight - 1, midnight - 2];
+        assert_eq!(thin(&times, NOW, 0), vec![true, true, false]);
+    }
+
+    #[test]
+    fn daily_to_weekly() {
+        // Six days old is kept daily, seven is weekly and the same week as the daily one
+        let times = [NOW - 7 * DAY + 1, NOW - 7 * DAY];
+        assert_eq!(thin(&times, NOW, 0), vec![true, false]);
+    }
+
+    #[test]
+    fn week_boundary() {
+        let week = NOW / (7 * DAY) * (7 * DAY) - 7 * DAY;
+        let times = [week + 1, week - 1, week - DAY, week - 7 * DAY - 1];
+        assert_eq!(thin(&times, NOW, 0), vec![true, true, false, true]);
+    }
+
+    #[test]
+    fn too_old() {
+        let times = [NOW - WEEKLY_WEEKS * 7 * DAY];
+        assert_eq!(thin(&times, NOW, 0), vec![false]);
+        assert_eq!(thin(&times, NOW, 1), vec![true]);
+    }
+
+    #[test]
+    fn rotate_labels() {
+        let path = env::temp_dir().join(format!("backup-{}-rotate", process::id()));
+        let dir = path.join(BACKUP_FOLDER);
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&dir).unwrap();
+
+        let times = [
+            NOW,
+            NOW - HOUR,
+            NOW - 2 * HOUR,
+            NOW - 2 * DAY,
+            NOW - 60 * DAY,
+        ];
+        for label in [world_label("A"), ALL_SAVES_LABEL.to_owned()] {
+            for time in times {
+                fs::write(dir.join(backup_name(&label, time)), b"").unwrap();
+            }
+        }
+
+        // Each label keeps its newest two, the one from two days ago, and loses the rest
+        assert_eq!(rotate_at(&path, 2, NOW), 4);
+        let left = list_backups(&path);
+        for label in [world_label("A"), ALL_SAVES_LABEL.to_owned()] {
+            let times = left
+                .iter()
+                .filter(|x| x.label == label)
+                .map(|x| x.time)
+                .collect::<Vec<_>>();
+            assert_eq!(times, vec![NOW, NOW - HOUR, NOW - 2 * DAY], "{}", label);
+        }
+
+        assert_eq!(rotate_at(&path, 2, NOW), 0);
+        fs::remove_dir_all(path).unwrap();
+    }
+}
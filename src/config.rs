@@ -1,20 +1,38 @@
 use std::fs;
-use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
 
 use simple_config_parser::config;
-use tar;
 
+use crate::resource_pack::Compression;
+use crate::snapshot;
 use crate::VERSION;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub game_path: PathBuf,
 
+    // Enabled resource packs, highest priority first
+    pub resource_packs: Vec<String>,
+
+    // Resize pack images that don't match the game's originals
+    pub fix_images: bool,
+
+    // Compression used when making default.tar
+    pub default_compression: Compression,
+
+    // World to load when launching, and the folder the game saves to if it isn't `saves` in the game's folder
+    pub world: Option<PathBuf>,
+    pub save_path: Option<PathBuf>,
+
+    // Days to keep deleted worlds before purging them, 0 keeps them forever
+    pub trash_days: u32,
+
+    // Backups of each world to keep before thinning them out, 0 turns backups off
+    pub backup_count: u32,
+
     // Game Settings
     pub volume: u8,
     pub full_screen: bool,
@@ -25,6 +43,11 @@ pub enum ConfigUpdate {
     Volume(u8),
     FullScreen(bool),
     GamePath(String),
+    FixImages(bool),
+    DefaultCompression(Compression),
+    SavePath(String),
+    TrashDays(u32),
+    BackupCount(u32),
 }
 
 impl Config {
@@ -37,6 +60,44 @@ impl Config {
         cfg.parse(&data.replace('\r', "")).ok()?;
 
         let game_path = cfg.get("game_path")?;
+        let resource_packs = cfg
+            .get("resource_packs")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|x| match unescape_name(x.trim()) {
+                Ok(i) => Some(i),
+                Err(e) => {
+                    println!(
+                        "[!] Skipping resource pack '{}' in config.cfg: {}",
+                        x.trim(),
+                        e
+                    );
+                    None
+                }
+            })
+            .filter(|x| !x.is_empty())
+            .collect();
+
+        let fix_images = cfg.get("fix_images").unwrap_or_default() == "true";
+        let default_compression = cfg
+            .get("default_compression")
+            .and_then(|x| Compression::parse(&x))
+            .unwrap_or_default();
+        let path = |key| {
+            cfg.get(key)
+                .filter(|x: &String| !x.trim().is_empty())
+                .map(|x| Path::new(x.trim()).to_path_buf())
+        };
+        let world = path("world");
+        let save_path = path("save_path");
+        let trash_days = cfg
+            .get("trash_days")
+            .and_then(|x| x.trim().parse().ok())
+            .unwrap_or(Config::default().trash_days);
+        let backup_count = cfg
+            .get("backup_count")
+            .and_then(|x| x.trim().parse().ok())
+            .unwrap_or(Config::default().backup_count);
 
         let game_config = fs::read_to_string(Path::new(&game_path).join("freeways.cfg")).ok()?;
 
@@ -44,6 +105,13 @@ impl Config {
 
         Some(Config {
             game_path: Path::new(&game_path).to_path_buf(),
+            resource_packs,
+            fix_images,
+            default_compression,
+            world,
+            save_path,
+            trash_days,
+            backup_count,
             volume: game_config_data.clone().nth(7).unwrap().parse().ok()?,
             full_screen: game_config_data.nth(1).unwrap() == "true",
             ..Config::default()
@@ -66,6 +134,35 @@ impl Config {
                 game_path: Path::new(&game_path).to_path_buf(),
                 ..self.clone()
             },
+
+            ConfigUpdate::FixImages(fix_images) => Config {
+                fix_images,
+                ..self.clone()
+            },
+
+            ConfigUpdate::DefaultCompression(default_compression) => Config {
+                default_compression,
+                ..self.clone()
+            },
+
+            ConfigUpdate::SavePath(save_path) => Config {
+                save_path: if save_path.is_empty() {
+                    None
+                } else {
+                    Some(Path::new(&save_path).to_path_buf())
+                },
+                ..self.clone()
+            },
+
+            ConfigUpdate::TrashDays(trash_days) => Config {
+                trash_days,
+                ..self.clone()
+            },
+
+            ConfigUpdate::BackupCount(backup_count) => Config {
+                backup_count,
+                ..self.clone()
+            },
         }
     }
 
@@ -75,9 +172,20 @@ impl Config {
         fs::write(
             path,
             format!(
-                "; Freeways-Launcher V{} Config\ngame_path = {}\n",
+                "; Freeways-Launcher V{} Config\ngame_path = {}\nresource_packs = {}\nfix_images = {}\ndefault_compression = {}\nworld = {}\nsave_path = {}\ntrash_days = {}\nbackup_count = {}\n",
                 VERSION,
-                self.game_path.to_string_lossy()
+                self.game_path.to_string_lossy(),
+                self.resource_packs
+                    .iter()
+                    .map(|x| escape_name(x))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.fix_images,
+                self.default_compression,
+                path_text(&self.world),
+                path_text(&self.save_path),
+                self.trash_days,
+                self.backup_count
             ),
         )
         .ok()?;
@@ -92,12 +200,60 @@ impl Default for Config {
         Config {
             game_path: Path::new(r#"C:\Program Files\Steam\steamapps\common\Freeways"#)
                 .to_path_buf(),
+            resource_packs: Vec::new(),
+            fix_images: false,
+            default_compression: Compression::None,
+            world: None,
+            save_path: None,
+            trash_days: 30,
+            backup_count: 10,
             volume: 100,
             full_screen: false,
         }
     }
 }
 
+fn path_text(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Characters that would split or end a pack list value, written as `%XX` instead
+const ESCAPED: [char; 5] = ['%', ',', '=', ';', '#'];
+
+fn escape_name(name: &str) -> String {
+    name.chars()
+        .map(|x| {
+            if ESCAPED.contains(&x) {
+                format!("%{:02X}", x as u32)
+            } else {
+                x.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Undo `escape_name`, failing on a `%` that isn't followed by one of the escaped characters
+fn unescape_name(name: &str) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = name.chars();
+    while let Some(x) = chars.next() {
+        if x != '%' {
+            out.push(x);
+            continue;
+        }
+
+        let code = chars.by_ref().take(2).collect::<String>();
+        match u8::from_str_radix(&code, 16).map(char::from) {
+            Ok(x) if ESCAPED.contains(&x) => out.push(x),
+            _ => return Err(format!("`%{}` isn't a valid escape", code)),
+        }
+    }
+
+    Ok(out)
+}
+
 pub fn check_data_dir(path: PathBuf) -> Option<()> {
     // Make a default config file
     if !path.join("config.cfg").exists() {
@@ -111,23 +267,65 @@ pub fn check_data_dir(path: PathBuf) -> Option<()> {
 
     // Add defult rescorses from game dir into a resource pack
     if !path.join("resource_packs").join("default.tar").exists() {
-        let game_path = Config::load(path.join("config.cfg"))?.game_path;
+        let config = Config::load(path.join("config.cfg"))?;
+        snapshot::write_default(&path, &config.game_path, config.default_compression)?;
+    }
 
-        let file = File::create(path.join("resource_packs").join("default.tar")).ok()?;
-        let mut a = tar::Builder::new(file);
+    Some(())
+}
 
-        let data: &[u8] = "; Default Freeways Resources\n; Generated by Freeways-Launcher (https://github.com/Basicprogrammer10/Freeways-Lancher)\nname = Default\nauthor = Captain Games\nversion = 1.0\n".as_bytes();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let mut header = tar::Header::new_gnu();
-        header.set_path("pack.meta").unwrap();
-        header.set_size(data.len() as u64);
-        header.set_mtime(SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs());
-        header.set_cksum();
+    #[test]
+    fn escape_round_trip() {
+        for name in [
+            "Plain.tar",
+            "100%.tar",
+            "a,b=c;d#e%f.zip",
+            "%%,,",
+            "%2C",
+            "Café ✅ pack",
+            "",
+        ] {
+            let escaped = escape_name(name);
+            assert!(
+                !escaped.contains([',', '=', ';', '#'].as_ref()),
+                "{}",
+                escaped
+            );
+            assert_eq!(unescape_name(&escaped).unwrap(), name);
+        }
+        assert_eq!(escape_name("a,b%"), "a%2Cb%25");
+    }
 
-        a.append(&header, data).unwrap();
-        a.append_dir_all("assets", game_path.join("data")).ok()?;
-        a.into_inner().ok()?;
+    #[test]
+    fn unescape_lowercase() {
+        assert_eq!(unescape_name("a%2cb%3b").unwrap(), "a,b;");
     }
 
-    Some(())
+    #[test]
+    fn unescape_malformed() {
+        for name in [
+            "%",
+            "a%",
+            "a%2",
+            "%G0",
+            "%+2",
+            "%-1",
+            "%41",
+            "%00",
+            "%7F",
+            "%é",
+            "%2é",
+            "a%✅✅b",
+        ] {
+            assert!(unescape_name(name).is_err(), "{}", name);
+        }
+        assert_eq!(
+            unescape_name("a%2").unwrap_err(),
+            "`%2` isn't a valid escape"
+        );
+    }
 }
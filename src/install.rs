@@ -0,0 +1,184 @@
+use std::fs;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A set of file writes that can be undone.
+/// Every file that gets overwritten is copied into the backup folder first.
+#[derive(Debug)]
+pub struct Transaction {
+    backup_path: PathBuf,
+    changes: Vec<Change>,
+    staged: usize,
+}
+
+#[derive(Debug)]
+enum Change {
+    Created(PathBuf),
+    CreatedDir(PathBuf),
+    Replaced { path: PathBuf, backup: PathBuf },
+    Removed { path: PathBuf, backup: PathBuf },
+    RemovedDir(PathBuf),
+}
+
+impl Transaction {
+    /// Start a transaction with its own folder in `backup_root`.
+    /// Folders left by a failed rollback are never reused, they hold the only copies of those files.
+    pub fn new(backup_root: PathBuf) -> Option<Transaction> {
+        fs::create_dir_all(&backup_root).ok()?;
+
+        let time = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let mut n = 0;
+        let backup_path = loop {
+            let i = backup_root.join(format!("{}-{}", time, n));
+            match fs::create_dir(&i) {
+                Ok(_) => break i,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+                Err(_) => return None,
+            }
+        };
+
+        Some(Transaction {
+            backup_path,
+            changes: Vec::new(),
+            staged: 0,
+        })
+    }
+
+    pub fn write(&mut self, path: &Path, data: &[u8]) -> Option<()> {
+        self.prepare(path)?;
+        fs::write(path, data).ok()
+    }
+
+    /// Write a file from a reader without holding it all in memory
+    pub fn write_from(&mut self, path: &Path, reader: &mut dyn Read) -> Option<()> {
+        self.prepare(path)?;
+        io::copy(reader, &mut File::create(path).ok()?).ok()?;
+        Some(())
+    }
+
+    /// Copy a file into the transaction's folder without touching the game's files,
+    /// so it can be checked before `write_staged` puts it in place
+    pub fn stage(&mut self, reader: &mut dyn Read) -> Option<PathBuf> {
+        let path = self.backup_path.join(format!("staged-{}", self.staged));
+        self.staged += 1;
+        io::copy(reader, &mut File::create(&path).ok()?).ok()?;
+        Some(path)
+    }
+
+    /// Move a file made by `stage` to `path`
+    pub fn write_staged(&mut self, path: &Path, staged: &Path) -> Option<()> {
+        self.prepare(path)?;
+        if fs::rename(staged, path).is_err() {
+            fs::copy(staged, path).ok()?;
+            fs::remove_file(staged).ok()?;
+        }
+        Some(())
+    }
+
+    pub fn remove(&mut self, path: &Path) -> Option<()> {
+        let backup = self.backup_path.join(self.changes.len().to_string());
+        fs::copy(path, &backup).ok()?;
+        fs::remove_file(path).ok()?;
+        self.changes.push(Change::Removed {
+            path: path.to_path_buf(),
+            backup,
+        });
+
+        Some(())
+    }
+
+    /// Remove an empty folder
+    pub fn remove_dir(&mut self, path: &Path) -> Option<()> {
+        fs::remove_dir(path).ok()?;
+        self.changes.push(Change::RemovedDir(path.to_path_buf()));
+        Some(())
+    }
+
+    /// Remove the folders holding `path` that are now empty, stopping at `root`
+    pub fn remove_empty_dirs(&mut self, root: &Path, path: &Path) -> Option<()> {
+        for i in path.ancestors().skip(1) {
+            if i == root || !i.starts_with(root) || fs::read_dir(i).ok()?.next().is_some() {
+                break;
+            }
+            self.remove_dir(i)?;
+        }
+
+        Some(())
+    }
+
+    /// Backup the file at `path` if it exists, or create its parent folders if not
+    fn prepare(&mut self, path: &Path) -> Option<()> {
+        if path.is_file() {
+            let backup = self.backup_path.join(self.changes.len().to_string());
+            fs::copy(path, &backup).ok()?;
+            self.changes.push(Change::Replaced {
+                path: path.to_path_buf(),
+                backup,
+            });
+            return Some(());
+        }
+
+        let mut dirs = Vec::new();
+        let mut parent = path.parent();
+        while let Some(i) = parent {
+            if i.exists() {
+                break;
+            }
+            dirs.push(i.to_path_buf());
+            parent = i.parent();
+        }
+
+        for i in dirs.into_iter().rev() {
+            fs::create_dir(&i).ok()?;
+            self.changes.push(Change::CreatedDir(i));
+        }
+
+        self.changes.push(Change::Created(path.to_path_buf()));
+        Some(())
+    }
+
+    /// Keep all changes and remove the backups
+    pub fn commit(self) {
+        self.remove_backups();
+    }
+
+    /// Undo every change made so far, newest first.
+    /// The backups are kept if any of them could not be restored.
+    pub fn rollback(self) {
+        let mut restored = true;
+
+        for i in self.changes.iter().rev() {
+            match i {
+                Change::Created(path) => fs::remove_file(path).unwrap_or_default(),
+                Change::CreatedDir(path) => fs::remove_dir(path).unwrap_or_default(),
+                Change::RemovedDir(path) => fs::create_dir(path).unwrap_or_default(),
+                Change::Replaced { path, backup } | Change::Removed { path, backup } => {
+                    if fs::copy(backup, path).is_err() {
+                        println!("[!] Failed to restore {}", path.to_string_lossy());
+                        restored = false;
+                    }
+                }
+            }
+        }
+
+        if restored {
+            self.remove_backups();
+        } else {
+            println!(
+                "[!] The original files were kept in {}",
+                self.backup_path.to_string_lossy()
+            );
+        }
+    }
+
+    /// Remove this transaction's backups, and the backup folder if nothing else is left in it
+    fn remove_backups(&self) {
+        fs::remove_dir_all(&self.backup_path).unwrap_or_default();
+        if let Some(i) = self.backup_path.parent() {
+            fs::remove_dir(i).unwrap_or_default();
+        }
+    }
+}
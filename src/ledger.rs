@@ -0,0 +1,403 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::install::Transaction;
+use crate::resource_pack::ResourcePack;
+
+/// Folder in the launcher's data folder that ledgers are kept in, one for each game install
+pub const LEDGER_FOLDER: &str = "ledgers";
+
+/// Record of every file the launcher has written into the game's data folder.
+/// Kept in the launcher's data folder next to the originals, so the game's files are only ever
+/// the game's and the packs'.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Ledger {
+    /// Installed packs, in the order they were installed
+    pub packs: Vec<InstalledPack>,
+
+    /// Every file written by a pack, with the packs that wrote it oldest first
+    pub files: BTreeMap<String, Vec<Layer>>,
+
+    #[serde(skip)]
+    data_path: PathBuf,
+    #[serde(skip)]
+    store_path: PathBuf,
+    #[serde(skip)]
+    ledger_path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledPack {
+    pub name: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Layer {
+    pub pack: String,
+    pub hash: String,
+
+    /// Hash of the file this layer overwrote, or None if there wasn't one.
+    /// The old file is kept in the launcher's originals folder under this hash.
+    pub replaced: Option<String>,
+
+    /// Set on an image resized by `fix_images`, which is kept as its own layer on top of the
+    /// pack's signed copy. It isn't covered by the pack's manifest or signature, and the signed
+    /// copy it replaced was never written, so isn't in the originals folder.
+    #[serde(default)]
+    pub resized: bool,
+}
+
+impl Ledger {
+    pub fn load(path: &Path, game_path: &Path) -> Ledger {
+        let ledger_path = ledger_path(path, game_path);
+        let ledger = match fs::read(&ledger_path) {
+            Ok(i) => serde_json::from_slice(&i).unwrap_or_else(|_| {
+                println!("[!] Resource pack ledger is corrupt, starting a new one");
+                Ledger::default()
+            }),
+            Err(_) => Ledger::default(),
+        };
+
+        Ledger {
+            data_path: game_path.join("data"),
+            store_path: path.join("originals"),
+            ledger_path,
+            ..ledger
+        }
+    }
+
+    /// Where the original of a file replaced by a pack is kept, by its hash
+    pub fn original(&self, hash: &str) -> PathBuf {
+        self.store_path.join(hash)
+    }
+
+    fn save(&self, transaction: &mut Transaction) -> Option<()> {
+        let path = &self.ledger_path;
+
+        if self.packs.is_empty() && self.files.is_empty() {
+            if path.exists() {
+                transaction.remove(path)?;
+            }
+            return Some(());
+        }
+
+        let data = serde_json::to_vec_pretty(self).ok()?;
+        transaction.write(path, &data)
+    }
+
+    /// Write a pack's assets on top of whatever is installed.
+    /// Packs that fail verification against their manifest or signature are refused.
+    /// The pack is read once into the transaction's folder, hashing each file as it is streamed in,
+    /// and only written to the game's folder once it is verified, so the bytes checked are the
+    /// bytes installed. A pack that fails is rolled back with the rest of the transaction.
+    /// Returns the files that were changed by hand, these are left alone.
+    pub fn install(
+        &mut self,
+        transaction: &mut Transaction,
+        pack: &ResourcePack,
+    ) -> Option<Vec<String>> {
+        if let Err(e) = pack.check_signature() {
+            println!(
+                "[!] '{}' has an invalid signature, not installing it: {}",
+                pack.name, e
+            );
+            return None;
+        }
+
+        let (files, data_path) = (&self.files, &self.data_path);
+        let mut hashes = BTreeMap::new();
+        let mut staged = Vec::new();
+        let mut edited = Vec::new();
+
+        let verified = pack.for_each_hashed(|file, reader| {
+            let mut reader = HashReader::new(reader);
+            if let Some(asset) = file.asset() {
+                let key = asset_key(asset);
+                let path = data_path.join(asset);
+                let current_hash = hash_file(&path);
+                let top = files.get(&key).and_then(|x| x.last());
+
+                match top {
+                    Some(top) if Some(&top.hash) != current_hash.as_ref() => {
+                        println!(
+                            "[!] {} was changed by hand since '{}' installed it, leaving it",
+                            key, top.pack
+                        );
+                        edited.push(key);
+                    }
+                    _ => {
+                        // Keep the file this pack replaces, unless it is this pack's own
+                        let original = current_hash
+                            .as_ref()
+                            .filter(|_| top.map(|x| &x.pack) != Some(&pack.name))
+                            .is_some();
+                        let from = transaction.stage(&mut reader)?;
+                        staged.push((
+                            file.name.to_owned(),
+                            key,
+                            path,
+                            current_hash,
+                            original,
+                            from,
+                        ));
+                    }
+                }
+            }
+
+            hashes.insert(file.name.to_owned(), reader.finish().ok()?);
+            Some(())
+        })?;
+
+        let verification = pack.verify_hashes(&verified);
+        if !verification.passed() {
+            println!(
+                "[!] '{}' doesn't match its manifest, not installing it: {}",
+                pack.name, verification
+            );
+            return None;
+        }
+
+        fs::create_dir_all(&self.store_path).ok()?;
+        let mut written = Vec::new();
+        for (name, key, path, current_hash, keep, from) in staged {
+            if let Some(hash) = current_hash.as_ref().filter(|_| keep) {
+                let original = self.store_path.join(hash);
+                if !original.exists() {
+                    fs::copy(&path, original).ok()?;
+                }
+            }
+
+            transaction.write_staged(&path, &from)?;
+            written.push((name, key, current_hash));
+        }
+
+        for (name, key, current_hash) in written {
+            let layers = self.files.entry(key).or_default();
+
+            // Reinstalling replaces the pack's own layers, keeping what they replaced
+            let own = layers
+                .iter()
+                .rev()
+                .take_while(|x| x.pack == pack.name)
+                .count();
+            let replaced = match own {
+                0 => current_hash,
+                _ => layers[layers.len() - own].replaced.to_owned(),
+            };
+            layers.truncate(layers.len() - own);
+
+            layers.push(Layer {
+                pack: pack.name.to_owned(),
+                hash: verified[&name].to_owned(),
+                replaced,
+                resized: false,
+            });
+            if hashes[&name] != verified[&name] {
+                layers.push(Layer {
+                    pack: pack.name.to_owned(),
+                    hash: hashes[&name].to_owned(),
+                    replaced: Some(verified[&name].to_owned()),
+                    resized: true,
+                });
+            }
+        }
+
+        let installed = InstalledPack {
+            name: pack.name.to_owned(),
+            hash: hashes_hash(&hashes),
+        };
+        match self.packs.iter_mut().find(|x| x.name == pack.name) {
+            Some(i) => *i = installed,
+            None => self.packs.push(installed),
+        }
+
+        Some(edited)
+    }
+
+    /// Remove a pack, putting back only the files it owns.
+    /// Returns the files that were changed by hand, these are left alone.
+    pub fn uninstall(&mut self, transaction: &mut Transaction, name: &str) -> Option<Vec<String>> {
+        let mut edited = Vec::new();
+
+        for (key, layers) in self.files.iter_mut() {
+            let pos = match layers.iter().position(|x| x.pack == name) {
+                Some(i) => i,
+                None => continue,
+            };
+            // A pack's resized images sit on top of its own layer
+            let end = pos + layers[pos..].iter().take_while(|x| x.pack == name).count();
+            let removed = layers.drain(pos..end).collect::<Vec<_>>();
+            let (layer, top) = (&removed[0], &removed[removed.len() - 1]);
+
+            // A later pack overwrote this file, so it now replaces what we replaced
+            if pos < layers.len() {
+                layers[pos].replaced = layer.replaced.to_owned();
+                continue;
+            }
+
+            let path = self.data_path.join(key);
+            if hash_file(&path).as_ref() != Some(&top.hash) {
+                println!(
+                    "[!] {} was changed by hand since '{}' installed it, leaving it",
+                    key, name
+                );
+                edited.push(key.to_owned());
+                continue;
+            }
+
+            match &layer.replaced {
+                Some(i) => {
+                    let mut original = File::open(self.store_path.join(i)).ok()?;
+                    transaction.write_from(&path, &mut original)?
+                }
+                None => {
+                    transaction.remove(&path)?;
+                    transaction.remove_empty_dirs(&self.data_path, &path)?;
+                }
+            }
+        }
+
+        self.files.retain(|_, x| !x.is_empty());
+        self.packs.retain(|x| x.name != name);
+        Some(edited)
+    }
+
+    /// Make the installed packs match `packs`, given in install order.
+    /// Packs that are already installed in the right place are left as they are.
+    pub fn apply(
+        &mut self,
+        transaction: &mut Transaction,
+        packs: &[&ResourcePack],
+    ) -> Option<Vec<String>> {
+        let keep = self
+            .packs
+            .iter()
+            .zip(packs)
+            .take_while(|(a, b)| a.name == b.name && pack_hash(b).as_ref() == Some(&a.hash))
+            .count();
+
+        let mut edited = Vec::new();
+        let remove = self.packs[keep..]
+            .iter()
+            .rev()
+            .map(|x| x.name.to_owned())
+            .collect::<Vec<_>>();
+        for i in remove {
+            println!("[*] Uninstalling '{}'", i);
+            edited.extend(self.uninstall(transaction, &i)?);
+        }
+
+        for i in &packs[keep..] {
+            println!("[*] Installing '{}'", i.name);
+            edited.extend(self.install(transaction, i)?);
+        }
+
+        Some(edited)
+    }
+}
+
+/// Run `f` on the game's ledger in a single transaction.
+/// The ledger is saved if `f` succeeds, otherwise every change is rolled back.
+pub fn transact<T>(
+    path: &Path,
+    game_path: &Path,
+    f: impl FnOnce(&mut Ledger, &mut Transaction) -> Option<T>,
+) -> Option<T> {
+    let mut ledger = Ledger::load(path, game_path);
+    let mut transaction = Transaction::new(path.join("backup"))?;
+
+    let out = match f(&mut ledger, &mut transaction) {
+        Some(i) if ledger.save(&mut transaction).is_some() => i,
+        _ => {
+            println!("[!] Failed to update resources, rolling back");
+            transaction.rollback();
+            return None;
+        }
+    };
+
+    transaction.commit();
+    Some(out)
+}
+
+/// Hash everything from a reader, a chunk at a time
+pub fn hash_reader(reader: impl Read) -> io::Result<String> {
+    HashReader::new(reader).finish()
+}
+
+/// A reader that hashes everything read through it
+pub struct HashReader<R> {
+    reader: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashReader<R> {
+    pub fn new(reader: R) -> HashReader<R> {
+        HashReader {
+            reader,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Hash of everything read so far
+    pub fn hash(&self) -> String {
+        format!("{:x}", self.hasher.clone().finalize())
+    }
+
+    /// Read whatever is left and get the hash of all of it
+    pub fn finish(mut self) -> io::Result<String> {
+        io::copy(&mut self, &mut io::sink())?;
+        Ok(self.hash())
+    }
+}
+
+impl<R: Read> Read for HashReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Hash of a file on disk, or None if it can't be read
+pub fn hash_file(path: &Path) -> Option<String> {
+    hash_reader(File::open(path).ok()?).ok()
+}
+
+/// Hash of every file in a pack, used to tell when a pack has changed
+pub fn pack_hash(pack: &ResourcePack) -> Option<String> {
+    Some(hashes_hash(&pack.hashes()?))
+}
+
+fn hashes_hash(hashes: &BTreeMap<String, String>) -> String {
+    let mut hasher = Sha256::new();
+    for (name, hash) in hashes {
+        hasher.update(name.as_bytes());
+        hasher.update(hash.as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Where the ledger of a game install is kept, named after the hash of the game's path
+pub fn ledger_path(path: &Path, game_path: &Path) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(game_path.to_string_lossy().as_bytes());
+    path.join(LEDGER_FOLDER)
+        .join(format!("{:x}.json", hasher.finalize()))
+}
+
+/// Key of an asset in the ledger, with `/` separators on every platform
+pub fn asset_key(asset: &Path) -> String {
+    asset
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
@@ -9,9 +9,19 @@ use image::GenericImageView;
 
 mod app;
 mod assets;
+mod backup;
 mod config;
+mod install;
+mod ledger;
+mod pack_stack;
 mod resource_pack;
+mod snapshot;
 mod style;
+mod trash;
+mod trust;
+mod version;
+mod world_archive;
+mod worlds;
 use app::App;
 
 pub const VERSION: &str = "α0.1.0";
@@ -60,12 +70,13 @@ pub fn main() -> iced::Result {
 
 // ✅ Config Saveing
 
-// Allow picking between diffrent worlds
-// Allow makeing new worlds
-// Allow deleteing worlds (move to like a /deleted folder)
+// ✅ Allow picking between diffrent worlds
+// ✅ Allow makeing new worlds
+// ✅ Allow deleteing worlds (move to like a /deleted folder)
+// ✅ Export / import worlds to share them
 
 // Rescorse pack loader
-//  * Load from zip / tar files
-//  * Load meta file from package
-//  * add gui stuff for adding / managing packs
-//  * alow resetting to orginal rescorses
+//  * ✅ Load from zip / tar files
+//  * ✅ Load meta file from package
+//  * ✅ add gui stuff for adding / managing packs
+//  * ✅ alow resetting to orginal rescorses
@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::ledger;
+use crate::resource_pack::{self, ResourcePack};
+use crate::version::Version;
+
+/// Several packs enabled at once.
+/// Packs are ordered by priority, the first pack wins when two provide the same asset.
+#[derive(Debug)]
+pub struct PackStack {
+    pub packs: Vec<ResourcePack>,
+}
+
+/// An asset provided by more than one pack in the stack
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub asset: PathBuf,
+    pub provider: String,
+    pub shadowed: Vec<String>,
+}
+
+impl PackStack {
+    /// Load the packs named in `names` from the resource_packs folder,
+    /// or say which pack couldn't be loaded
+    pub fn load(path: &Path, names: &[String]) -> Result<PackStack, String> {
+        let packs = names
+            .iter()
+            .map(|x| {
+                ResourcePack::load(path.join(x))
+                    .map_err(|e| format!("Failed to load resource pack '{}': {}", x, e))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(PackStack { packs })
+    }
+
+    /// Get every asset that is shadowed by a higher priority pack
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        let mut providers = BTreeMap::<&Path, Vec<&str>>::new();
+
+        for i in &self.packs {
+            for j in &i.files {
+                if let Some(asset) = j.asset() {
+                    providers.entry(asset).or_default().push(&i.name);
+                }
+            }
+        }
+
+        providers
+            .into_iter()
+            .filter(|x| x.1.len() > 1)
+            .map(|(asset, packs)| Conflict {
+                asset: asset.to_path_buf(),
+                provider: packs[0].to_owned(),
+                shadowed: packs[1..].iter().map(|x| x.to_string()).collect(),
+            })
+            .collect()
+    }
+
+    /// Check every pack's images against the original resources.
+    /// With `fix` set, mismatched images are resampled to match before they are installed.
+    /// Returns a line for every mismatch, and for any pack that couldn't be checked.
+    pub fn check_images(&mut self, path: &Path, fix: bool) -> Vec<String> {
+        let default = match resource_pack::load_default(path) {
+            Ok(i) => i,
+            Err(e) => {
+                return vec![format!(
+                    "Can't check images, failed to load default.tar: {}",
+                    e
+                )]
+            }
+        };
+
+        let mut out = Vec::new();
+        for i in self.packs.iter_mut() {
+            let mismatches = if fix {
+                i.fix_images(&default)
+            } else {
+                i.check_images(&default)
+            };
+
+            match mismatches {
+                Some(mismatches) => out.extend(mismatches.into_iter().map(|j| {
+                    if fix && j.error.is_none() && i.signature.is_some() {
+                        format!(
+                            "{} in '{}', resized it, the resized copy isn't signed",
+                            j, i.name
+                        )
+                    } else if fix && j.error.is_none() {
+                        format!("{} in '{}', resized it", j, i.name)
+                    } else {
+                        format!("{} in '{}'", j, i.name)
+                    }
+                })),
+                None => out.push(format!("Failed to check the images in '{}'", i.name)),
+            }
+        }
+
+        for i in &out {
+            println!("[!] {}", i);
+        }
+        out
+    }
+
+    /// Install every pack in the stack, lowest priority first, in one transaction.
+    /// Packs no longer in the stack are uninstalled.
+    /// Returns the files that were changed by hand and left alone.
+    pub fn install(&self, path: &Path, game_path: &Path) -> Option<Vec<String>> {
+        for i in self.conflicts() {
+            println!(
+                "[*] {} from '{}' shadows {}",
+                i.asset.to_string_lossy(),
+                i.provider,
+                i.shadowed.join(", ")
+            );
+        }
+
+        let packs = self.packs.iter().rev().collect::<Vec<_>>();
+        ledger::transact(path, game_path, |ledger, transaction| {
+            ledger.apply(transaction, &packs)
+        })
+    }
+}
+
+/// The parts of an installed pack needed to work out which packs can be enabled together
+#[derive(Debug, Clone)]
+pub struct PackDeps {
+    pub file: String,
+    pub name: String,
+    pub version: String,
+    pub requires: Vec<resource_pack::PackRef>,
+    pub conflicts: Vec<resource_pack::PackRef>,
+}
+
+/// Add the packs that the packs in `files` require, and order them so every pack has a
+/// higher priority than the packs it requires, which are installed first.
+/// Returns the new priority order, or why the packs can't be enabled together.
+pub fn resolve_dependencies(
+    available: &[PackDeps],
+    files: &[String],
+) -> Result<Vec<String>, String> {
+    // Indexes into `available` of the packs to enable, by priority
+    let mut set = files
+        .iter()
+        .filter_map(|x| available.iter().position(|y| y.file == *x))
+        .collect::<Vec<_>>();
+
+    let mut i = 0;
+    while i < set.len() {
+        let pack = &available[set[i]];
+        for req in &pack.requires {
+            let enabled = set
+                .iter()
+                .map(|x| &available[*x])
+                .find(|x| req.is_named(&x.name));
+            match enabled {
+                Some(dep) if req.matches_version(&dep.name, &dep.version) => {}
+                Some(dep) => {
+                    return Err(format!(
+                        "{} needs {}, but {} {} is enabled",
+                        pack.name, req, dep.name, dep.version
+                    ))
+                }
+                None => set.push(best_match(available, pack, req)?),
+            }
+        }
+        i += 1;
+    }
+
+    for (i, a) in set.iter().enumerate() {
+        let pack = &available[*a];
+        if let Some(b) = set[i + 1..]
+            .iter()
+            .find(|x| available[**x].name == pack.name)
+        {
+            return Err(format!(
+                "{} is enabled twice, by {} and {}",
+                pack.name, available[*a].file, available[*b].file
+            ));
+        }
+
+        for c in &pack.conflicts {
+            if let Some(other) = set
+                .iter()
+                .map(|x| &available[*x])
+                .find(|x| c.matches_version(&x.name, &x.version))
+            {
+                return Err(format!(
+                    "{} conflicts with {} {}",
+                    pack.name, other.name, other.version
+                ));
+            }
+        }
+    }
+
+    let mut order = Vec::new();
+    for i in set.iter().rev() {
+        visit(available, &set, *i, &mut order, &mut Vec::new())?;
+    }
+
+    Ok(order
+        .into_iter()
+        .rev()
+        .map(|x| available[x].file.to_owned())
+        .collect())
+}
+
+/// Find the newest pack that fills a requirement, explaining why if there isn't one
+fn best_match(
+    available: &[PackDeps],
+    pack: &PackDeps,
+    req: &resource_pack::PackRef,
+) -> Result<usize, String> {
+    if let Some(i) = (0..available.len())
+        .filter(|x| req.matches_version(&available[*x].name, &available[*x].version))
+        .max_by_key(|x| Version::parse(&available[*x].version))
+    {
+        println!(
+            "[*] Enabling '{}', needed by '{}'",
+            available[i].name, pack.name
+        );
+        return Ok(i);
+    }
+
+    let versions = available
+        .iter()
+        .filter(|x| req.is_named(&x.name))
+        .map(|x| x.version.to_owned())
+        .collect::<Vec<_>>();
+    if versions.is_empty() {
+        Err(format!(
+            "{} needs {}, which isn't installed",
+            pack.name, req
+        ))
+    } else {
+        Err(format!(
+            "{} needs {}, but only version {} is installed",
+            pack.name,
+            req,
+            versions.join(", ")
+        ))
+    }
+}
+
+/// Add a pack to the install order after everything it requires
+fn visit(
+    available: &[PackDeps],
+    set: &[usize],
+    i: usize,
+    order: &mut Vec<usize>,
+    stack: &mut Vec<usize>,
+) -> Result<(), String> {
+    if order.contains(&i) {
+        return Ok(());
+    }
+    if let Some(pos) = stack.iter().position(|x| *x == i) {
+        let cycle = stack[pos..]
+            .iter()
+            .chain([&i])
+            .map(|x| available[*x].name.as_str())
+            .collect::<Vec<_>>();
+        return Err(format!("Packs require each other: {}", cycle.join(" -> ")));
+    }
+
+    stack.push(i);
+    for req in &available[i].requires {
+        if let Some(dep) = set.iter().find(|x| req.is_named(&available[**x].name)) {
+            visit(available, set, *dep, order, stack)?;
+        }
+    }
+    stack.pop();
+    order.push(i);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resource_pack::PackRef;
+
+    fn pack(file: &str, name: &str, version: &str, requires: &str, conflicts: &str) -> PackDeps {
+        PackDeps {
+            file: file.to_owned(),
+            name: name.to_owned(),
+            version: version.to_owned(),
+            requires: PackRef::parse_list(requires).unwrap(),
+            conflicts: PackRef::parse_list(conflicts).unwrap(),
+        }
+    }
+
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn available() -> Vec<PackDeps> {
+        vec![
+            pack("ui.tar", "UI", "2", "Base Fonts (1.2+)", ""),
+            pack("fonts-1.0.tar", "Base Fonts", "1.0", "", ""),
+            pack("fonts-1.5.tar", "Base Fonts", "1.5", "", ""),
+            pack("old.tar", "Old UI", "1", "", "UI (1 - 3)"),
+        ]
+    }
+
+    #[test]
+    fn adds_newest_dependency_below() {
+        let order = resolve_dependencies(&available(), &files(&["ui.tar"])).unwrap();
+        assert_eq!(order, files(&["ui.tar", "fonts-1.5.tar"]));
+    }
+
+    #[test]
+    fn raises_packs_above_dependencies() {
+        let order =
+            resolve_dependencies(&available(), &files(&["fonts-1.5.tar", "ui.tar"])).unwrap();
+        assert_eq!(order, files(&["ui.tar", "fonts-1.5.tar"]));
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let packs = vec![pack("ui.tar", "UI", "2", "Icons", "")];
+        let e = resolve_dependencies(&packs, &files(&["ui.tar"])).unwrap_err();
+        assert_eq!(e, "UI needs Icons, which isn't installed");
+    }
+
+    #[test]
+    fn dependency_too_old() {
+        let packs = vec![
+            pack("ui.tar", "UI", "2", "Base Fonts (2+)", ""),
+            pack("fonts.tar", "Base Fonts", "1.5", "", ""),
+        ];
+        let e = resolve_dependencies(&packs, &files(&["ui.tar"])).unwrap_err();
+        assert_eq!(
+            e,
+            "UI needs Base Fonts (2+), but only version 1.5 is installed"
+        );
+    }
+
+    #[test]
+    fn enabled_dependency_out_of_range() {
+        let e =
+            resolve_dependencies(&available(), &files(&["fonts-1.0.tar", "ui.tar"])).unwrap_err();
+        assert_eq!(
+            e,
+            "UI needs Base Fonts (1.2+), but Base Fonts 1.0 is enabled"
+        );
+    }
+
+    #[test]
+    fn conflict() {
+        let e = resolve_dependencies(&available(), &files(&["old.tar", "ui.tar"])).unwrap_err();
+        assert_eq!(e, "Old UI conflicts with UI 2");
+    }
+
+    #[test]
+    fn two_pack_cycle() {
+        let packs = vec![
+            pack("a.tar", "A", "1", "B", ""),
+            pack("b.tar", "B", "1", "A", ""),
+        ];
+        let e = resolve_dependencies(&packs, &files(&["a.tar"])).unwrap_err();
+        assert!(e.starts_with("Packs require each other: "), "{}", e);
+        assert!(
+            e.contains("A -> B -> A") || e.contains("B -> A -> B"),
+            "{}",
+            e
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+use tar::Archive;
+use zip::ZipArchive;
+
+use super::{list_files, Compression, LoadError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackFormat {
+    Tar(Compression),
+    Zip,
+    Dir,
+}
+
+impl PackFormat {
+    /// Work out the archive type from its magic bytes, falling back to the file extension.
+    /// Folders are loaded as unpacked packs.
+    pub fn detect(path: &Path) -> Option<PackFormat> {
+        if path.is_dir() {
+            return Some(PackFormat::Dir);
+        }
+
+        let mut head = Vec::new();
+        File::open(path)
+            .ok()?
+            .take(262)
+            .read_to_end(&mut head)
+            .ok()?;
+
+        if head.starts_with(b"PK\x03\x04") || head.starts_with(b"PK\x05\x06") {
+            return Some(PackFormat::Zip);
+        }
+
+        if is_tar(&head) {
+            return Some(PackFormat::Tar(Compression::None));
+        }
+
+        // Only accept compressed files that hold a tar
+        if let Some(i) = Compression::detect(&head) {
+            let mut head = Vec::new();
+            i.decoder(File::open(path).ok()?)
+                .ok()?
+                .take(262)
+                .read_to_end(&mut head)
+                .ok()?;
+            return is_tar(&head).then(|| PackFormat::Tar(i));
+        }
+
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".zip") {
+            return Some(PackFormat::Zip);
+        }
+        Compression::from_name(&name).map(PackFormat::Tar)
+    }
+}
+
+/// Check for the `ustar` magic in a tar's first header
+fn is_tar(head: &[u8]) -> bool {
+    head.len() >= 262 && &head[257..262] == b"ustar"
+}
+
+/// Called with each file's name, size and contents, returning `Break` to stop walking
+pub type Visit<'a> = dyn FnMut(&str, u64, &mut dyn Read) -> Result<ControlFlow<()>, LoadError> + 'a;
+
+/// Stream every file in a pack to `f` with its name and size.
+/// Only one file is open at a time, and every entry is checked before it is passed on.
+/// Returns `Break` if `f` stopped early.
+pub fn walk(path: &Path, format: PackFormat, f: &mut Visit) -> Result<ControlFlow<()>, LoadError> {
+    match format {
+        PackFormat::Tar(i) => walk_tar(path, i, f),
+        PackFormat::Zip => walk_zip(path, f),
+        PackFormat::Dir => walk_dir(path, f),
+    }
+}
+
+fn walk_tar(
+    path: &Path,
+    compression: Compression,
+    f: &mut Visit,
+) -> Result<ControlFlow<()>, LoadError> {
+    let mut a = Archive::new(compression.decoder(File::open(path)?)?);
+
+    for i in a.entries()? {
+        let mut file = i?;
+        let name = file.path()?.to_string_lossy().to_string();
+        let kind = file.header().entry_type();
+
+        if kind.is_dir() || kind.is_pax_global_extensions() {
+            continue;
+        }
+
+        let reason = if kind.is_symlink() {
+            Some("symbolic links are not allowed")
+        } else if kind.is_hard_link() {
+            Some("hard links are not allowed")
+        } else if kind.is_character_special() || kind.is_block_special() || kind.is_fifo() {
+            Some("device files are not allowed")
+        } else if !kind.is_file() && !kind.is_contiguous() {
+            Some("unsupported entry type")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(LoadError::UnsafeEntry {
+                entry: name,
+                reason,
+            });
+        }
+        check_path(&name)?;
+
+        let size = file.header().size()?;
+        if matches!(f(&name, size, &mut file)?, ControlFlow::Break(_)) {
+            return Ok(ControlFlow::Break(()));
+        }
+    }
+
+    Ok(ControlFlow::Continue(()))
+}
+
+fn walk_zip(path: &Path, f: &mut Visit) -> Result<ControlFlow<()>, LoadError> {
+    let mut a = ZipArchive::new(BufReader::new(File::open(path)?))?;
+
+    for i in 0..a.len() {
+        let mut file = a.by_index(i)?;
+        let name = file.name().to_owned();
+
+        if file.is_dir() {
+            continue;
+        }
+
+        // Zip files made on unix keep the file type in the mode bits
+        let reason = match file.unix_mode().map(|x| x & 0o170000) {
+            Some(0o120000) => Some("symbolic links are not allowed"),
+            Some(0o020000) | Some(0o060000) | Some(0o010000) => {
+                Some("device files are not allowed")
+            }
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            return Err(LoadError::UnsafeEntry {
+                entry: name,
+                reason,
+            });
+        }
+        check_path(&name)?;
+
+        let size = file.size();
+        if matches!(f(&name, size, &mut file)?, ControlFlow::Break(_)) {
+            return Ok(ControlFlow::Break(()));
+        }
+    }
+
+    Ok(ControlFlow::Continue(()))
+}
+
+/// Unpacked packs are read straight from disk every time,
+/// so edits to the folder are picked up without re-archiving.
+fn walk_dir(path: &Path, f: &mut Visit) -> Result<ControlFlow<()>, LoadError> {
+    for i in list_files(path)? {
+        let name = i
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let meta = fs::symlink_metadata(path.join(&i))?;
+        let reason = if !meta.is_file() {
+            Some("only regular files are allowed")
+        } else if is_hard_link(&meta) {
+            Some("hard links are not allowed")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            return Err(LoadError::UnsafeEntry {
+                entry: name,
+                reason,
+            });
+        }
+        check_path(&name)?;
+
+        if matches!(
+            f(&name, meta.len(), &mut File::open(path.join(&i))?)?,
+            ControlFlow::Break(_)
+        ) {
+            return Ok(ControlFlow::Break(()));
+        }
+    }
+
+    Ok(ControlFlow::Continue(()))
+}
+
+#[cfg(unix)]
+fn is_hard_link(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn is_hard_link(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+/// Make sure an entry can't be written outside of the game's data folder
+fn check_path(name: &str) -> Result<(), LoadError> {
+    let path = name.replace('\\', "/");
+
+    let reason = if path.starts_with('/') {
+        "absolute paths are not allowed"
+    } else if path.split('/').any(|x| x.contains(':')) {
+        "drive letters and `:` are not allowed in paths"
+    } else if path.split('/').any(|x| x == "..") {
+        "`..` is not allowed in paths"
+    } else {
+        return Ok(());
+    };
+
+    Err(LoadError::UnsafeEntry {
+        entry: name.to_owned(),
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::process;
+
+    use tar::{Builder, EntryType, Header};
+    use zip::write::FileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    use super::*;
+
+    const DOTDOT: &str = "`..` is not allowed in paths";
+    const ABSOLUTE: &str = "absolute paths are not allowed";
+    const COLON: &str = "drive letters and `:` are not allowed in paths";
+
+    const UNSAFE_NAMES: &[(&str, &str)] = &[
+        ("../pack.meta", DOTDOT),
+        ("textures/../../pack.meta", DOTDOT),
+        ("/etc/pack.meta", ABSOLUTE),
+        ("\\\\server\\pack.meta", ABSOLUTE),
+        ("C:/pack.meta", COLON),
+        ("C:pack.meta", COLON),
+        ("textures/C:pack.meta", COLON),
+        ("textures\\..\\..\\pack.meta", DOTDOT),
+    ];
+
+    fn tmp(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("archive-{}-{}", process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    fn names(path: &Path, format: PackFormat) -> Result<Vec<String>, LoadError> {
+        let mut out = Vec::new();
+        let _ = walk(path, format, &mut |name, _, _| {
+            out.push(name.to_owned());
+            Ok(ControlFlow::Continue(()))
+        })?;
+        Ok(out)
+    }
+
+    fn rejected(path: &Path, format: PackFormat) -> &'static str {
+        match names(path, format) {
+            Err(LoadError::UnsafeEntry { reason, .. }) => reason,
+            x => panic!("{} wasn't rejected: {:?}", path.display(), x),
+        }
+    }
+
+    /// Write a tar with one entry, setting the name by hand so unsafe names aren't refused
+    fn make_tar(path: &Path, name: &str, kind: EntryType) {
+        let mut header = Header::new_ustar();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(kind);
+        header.set_mode(0o644);
+        if kind.is_symlink() || kind.is_hard_link() {
+            header.set_link_name("pack.meta").unwrap();
+        }
+        header.set_size(4);
+        header.set_cksum();
+
+        let mut b = Builder::new(File::create(path).unwrap());
+        b.append(&header, &b"data"[..]).unwrap();
+        b.finish().unwrap();
+    }
+
+    /// Write a zip with one entry.
+    /// `FileOptions` only keeps the permission bits, so the file type is set in the central directory by hand.
+    fn make_zip(path: &Path, name: &str, mode: u32) {
+        let mut z = ZipWriter::new(File::create(path).unwrap());
+        z.start_file(
+            name,
+            FileOptions::default().compression_method(CompressionMethod::Stored),
+        )
+        .unwrap();
+        z.write_all(b"data").unwrap();
+        z.finish().unwrap();
+
+        let mut data = fs::read(path).unwrap();
+        let at = data.windows(4).position(|x| x == b"PK\x01\x02").unwrap() + 38;
+        data[at..at + 4].copy_from_slice(&(mode << 16).to_le_bytes());
+        fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn check_paths() {
+        assert!(check_path("pack.meta").is_ok());
+        assert!(check_path("textures/ui/button.png").is_ok());
+        assert!(check_path("textures\\ui\\button.png").is_ok());
+        assert!(check_path("textures/..png").is_ok());
+
+        for (name, reason) in UNSAFE_NAMES {
+            match check_path(name) {
+                Err(LoadError::UnsafeEntry { entry, reason: x }) => {
+                    assert_eq!(entry, *name);
+                    assert_eq!(x, *reason, "{}", name);
+                }
+                x => panic!("{} wasn't rejected: {:?}", name, x),
+            }
+        }
+    }
+
+    #[test]
+    fn tar_names() {
+        let dir = tmp("tar-names");
+        let path = dir.join("pack.tar");
+
+        make_tar(&path, "textures/button.png", EntryType::Regular);
+        assert_eq!(
+            names(&path, PackFormat::Tar(Compression::None)).unwrap(),
+            vec!["textures/button.png"]
+        );
+
+        for (name, reason) in UNSAFE_NAMES {
+            make_tar(&path, name, EntryType::Regular);
+            assert_eq!(
+                rejected(&path, PackFormat::Tar(Compression::None)),
+                *reason,
+                "{}",
+                name
+            );
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn tar_entry_types() {
+        let dir = tmp("tar-types");
+        let path = dir.join("pack.tar");
+
+        let kinds = [
+            (EntryType::Symlink, "symbolic links are not allowed"),
+            (EntryType::Link, "hard links are not allowed"),
+            (EntryType::Char, "device files are not allowed"),
+            (EntryType::Block, "device files are not allowed"),
+            (EntryType::Fifo, "device files are not allowed"),
+        ];
+        for (kind, reason) in kinds.iter() {
+            make_tar(&path, "textures/button.png", *kind);
+            assert_eq!(
+                rejected(&path, PackFormat::Tar(Compression::None)),
+                *reason,
+                "{:?}",
+                kind
+            );
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn zip_names() {
+        let dir = tmp("zip-names");
+        let path = dir.join("pack.zip");
+
+        make_zip(&path, "textures/button.png", 0o100644);
+        assert_eq!(
+            names(&path, PackFormat::Zip).unwrap(),
+            vec!["textures/button.png"]
+        );
+
+        for (name, reason) in UNSAFE_NAMES {
+            make_zip(&path, name, 0o100644);
+            assert_eq!(rejected(&path, PackFormat::Zip), *reason, "{}", name);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn zip_entry_types() {
+        let dir = tmp("zip-types");
+        let path = dir.join("pack.zip");
+
+        // Zip has no hard links, a linked file is stored as a copy
+        let modes = [
+            (0o120777, "symbolic links are not allowed"),
+            (0o020644, "device files are not allowed"),
+            (0o060644, "device files are not allowed"),
+            (0o010644, "device files are not allowed"),
+        ];
+        for (mode, reason) in modes.iter() {
+            make_zip(&path, "textures/button.png", *mode);
+            assert_eq!(rejected(&path, PackFormat::Zip), *reason, "{:o}", mode);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dir_entries() {
+        use std::os::unix::fs::symlink;
+        use std::os::unix::net::UnixListener;
+
+        let dir = tmp("dir");
+        let pack = dir.join("pack");
+        fs::create_dir_all(pack.join("textures")).unwrap();
+        fs::write(pack.join("textures/button.png"), b"data").unwrap();
+        fs::write(dir.join("outside"), b"data").unwrap();
+        assert_eq!(
+            names(&pack, PackFormat::Dir).unwrap(),
+            vec!["textures/button.png"]
+        );
+
+        type Make<'a> = &'a dyn Fn(&Path);
+        let cases: [(&str, Make, &str); 5] = [
+            (
+                "link",
+                &|x| symlink(dir.join("outside"), x).unwrap(),
+                "only regular files are allowed",
+            ),
+            (
+                "socket",
+                &|x| drop(UnixListener::bind(x).unwrap()),
+                "only regular files are allowed",
+            ),
+            (
+                "hard",
+                &|x| fs::hard_link(dir.join("outside"), x).unwrap(),
+                "hard links are not allowed",
+            ),
+            ("C:pack.meta", &|x| fs::write(x, b"data").unwrap(), COLON),
+            ("..\\pack.meta", &|x| fs::write(x, b"data").unwrap(), DOTDOT),
+        ];
+        for (name, make, reason) in cases.iter() {
+            let path = pack.join("textures").join(name);
+            make(&path);
+            assert_eq!(rejected(&pack, PackFormat::Dir), *reason, "{}", name);
+            fs::remove_file(path).unwrap();
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use xz2::read::XzDecoder;
+use xz2::write::XzEncoder;
+
+/// Compression wrapped around a tar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+/// A tar being written with compression.
+/// Call `finish` when done, or the end of the stream may not be written.
+pub enum Encoder {
+    None(File),
+    Gzip(GzEncoder<File>),
+    Xz(XzEncoder<File>),
+    Zstd(zstd::Encoder<'static, File>),
+}
+
+impl Compression {
+    pub const ALL: [Compression; 4] = [
+        Compression::None,
+        Compression::Gzip,
+        Compression::Xz,
+        Compression::Zstd,
+    ];
+
+    /// Work out the compression from a file's first few bytes
+    pub fn detect(head: &[u8]) -> Option<Compression> {
+        if head.starts_with(b"\x1f\x8b") {
+            Some(Compression::Gzip)
+        } else if head.starts_with(b"\xfd7zXZ\x00") {
+            Some(Compression::Xz)
+        } else if head.starts_with(b"\x28\xb5\x2f\xfd") {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Work out the compression from a file name like `pack.tar.gz`
+    pub fn from_name(name: &str) -> Option<Compression> {
+        let name = name.to_lowercase();
+        let ends = |x: &[&str]| x.iter().any(|x| name.ends_with(x));
+
+        if ends(&[".tar"]) {
+            Some(Compression::None)
+        } else if ends(&[".tar.gz", ".tgz"]) {
+            Some(Compression::Gzip)
+        } else if ends(&[".tar.xz", ".txz"]) {
+            Some(Compression::Xz)
+        } else if ends(&[".tar.zst", ".tzst"]) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Parse the name used in the config file
+    pub fn parse(name: &str) -> Option<Compression> {
+        Compression::ALL
+            .iter()
+            .find(|x| x.to_string().eq_ignore_ascii_case(name.trim()))
+            .copied()
+    }
+
+    /// Open a file, decompressing it as it is read
+    pub fn decoder(&self, file: File) -> io::Result<Box<dyn Read>> {
+        let file = BufReader::new(file);
+
+        Ok(match self {
+            Compression::None => Box::new(file),
+            Compression::Gzip => Box::new(GzDecoder::new(file)),
+            Compression::Xz => Box::new(XzDecoder::new(file)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(file)?),
+        })
+    }
+
+    pub fn encoder(&self, file: File) -> io::Result<Encoder> {
+        Ok(match self {
+            Compression::None => Encoder::None(file),
+            Compression::Gzip => {
+                Encoder::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+            }
+            Compression::Xz => Encoder::Xz(XzEncoder::new(file, 6)),
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(file, 0)?),
+        })
+    }
+}
+
+impl Encoder {
+    /// Write the end of the compressed stream and flush it to disk
+    pub fn finish(self) -> io::Result<()> {
+        let mut file = match self {
+            Encoder::None(i) => i,
+            Encoder::Gzip(i) => i.finish()?,
+            Encoder::Xz(i) => i.finish()?,
+            Encoder::Zstd(i) => i.finish()?,
+        };
+
+        file.flush()
+    }
+}
+
+impl Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::None(i) => i.write(buf),
+            Encoder::Gzip(i) => i.write(buf),
+            Encoder::Xz(i) => i.write(buf),
+            Encoder::Zstd(i) => i.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::None(i) => i.flush(),
+            Encoder::Gzip(i) => i.flush(),
+            Encoder::Xz(i) => i.flush(),
+            Encoder::Zstd(i) => i.flush(),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Compression::None => "None",
+            Compression::Gzip => "Gzip",
+            Compression::Xz => "Xz",
+            Compression::Zstd => "Zstd",
+        })
+    }
+}
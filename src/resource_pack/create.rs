@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::Keypair;
+
+use super::manifest::{self, MANIFEST_FILE};
+use super::signature::{self, SIGNATURE_FILE};
+use super::{asset_path, list_files, load_default};
+use crate::ledger::{self, Ledger};
+use crate::trust;
+
+/// Add a file to a tar from memory
+pub fn append_file<W: Write>(a: &mut tar::Builder<W>, path: &str, data: &[u8]) -> Option<()> {
+    append_reader(a, path, data.len() as u64, data)
+}
+
+/// Add a file to a tar, streaming `size` bytes from `data`
+pub fn append_reader<W: Write>(
+    a: &mut tar::Builder<W>,
+    path: &str,
+    size: u64,
+    data: impl Read,
+) -> Option<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_path(path).ok()?;
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_mtime(SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs());
+    header.set_cksum();
+
+    a.append(&header, data).ok()
+}
+
+/// Package every file in the game's data folder that differs from `default.tar` into a new pack.
+/// Files installed by a pack that haven't been changed since are left out.
+/// With `sign` set the manifest is signed with the user's key, which is made if it is missing.
+/// Returns the new pack's file name and how many files are in it, or why it couldn't be made.
+pub fn create_pack(
+    path: &Path,
+    game_path: &Path,
+    name: &str,
+    author: &str,
+    sign: bool,
+) -> Result<(String, usize), String> {
+    let (name, author) = (name.trim(), author.trim());
+    check_names(name, author)?;
+
+    let changed = changed_files(path, game_path).ok_or("Failed to compare with default.tar")?;
+    if changed.is_empty() {
+        return Err("Nothing in the data folder has changed".to_owned());
+    }
+
+    let meta = format!(
+        "; Generated by Freeways-Launcher from changes to the game's data folder\nname = {}\nauthor = {}\nversion = 1.0\n",
+        name, author
+    );
+    let key = signing_key(path, sign)?;
+    let (file, to, out) = new_pack_file(path, name, author)?;
+    if write_pack(out, &meta, &game_path.join("data"), &changed, key.as_ref()).is_none() {
+        fs::remove_file(&to).unwrap_or_default();
+        return Err(format!("Failed to write {}", file));
+    }
+
+    Ok((file, changed.len()))
+}
+
+/// Check a new pack's name and author can go in `pack.meta`
+pub(super) fn check_names(name: &str, author: &str) -> Result<(), String> {
+    if name.is_empty() || author.is_empty() {
+        return Err("A name and author are needed".to_owned());
+    }
+    if name.contains(['=', ';', '#']) || author.contains(['=', ';', '#']) {
+        return Err("Names can't contain `=`, `;` or `#`".to_owned());
+    }
+
+    Ok(())
+}
+
+/// Load the signing key if the pack is to be signed
+pub(super) fn signing_key(path: &Path, sign: bool) -> Result<Option<Keypair>, String> {
+    if !sign {
+        return Ok(None);
+    }
+
+    trust::signing_key(path)
+        .map(Some)
+        .ok_or_else(|| "Failed to load the signing key".to_owned())
+}
+
+/// Check a new pack's name and author, and make an empty file for it in the resource_packs folder.
+/// If a pack already has the name, a number is added to the new one's.
+/// Returns the file name, its path and the open file.
+pub(super) fn new_pack_file(
+    path: &Path,
+    name: &str,
+    author: &str,
+) -> Result<(String, PathBuf, File), String> {
+    check_names(name, author)?;
+
+    let base = name.replace(|x: char| !x.is_alphanumeric() && !"-_ ".contains(x), "_");
+    let mut n = 1;
+    loop {
+        let file = if n == 1 {
+            format!("{}.tar", base)
+        } else {
+            format!("{} ({}).tar", base, n)
+        };
+        let to = path.join("resource_packs").join(&file);
+
+        match OpenOptions::new().write(true).create_new(true).open(&to) {
+            Ok(out) => return Ok((file, to, out)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(format!("Failed to create {}: {}", file, e)),
+        }
+    }
+}
+
+/// Write the pack with a manifest of every file in it, signed if there is a key
+fn write_pack(
+    out: File,
+    meta: &str,
+    data_path: &Path,
+    files: &BTreeMap<String, String>,
+    key: Option<&Keypair>,
+) -> Option<()> {
+    let mut a = tar::Builder::new(out);
+    append_file(&mut a, "pack.meta", meta.as_bytes())?;
+
+    let mut hashes = BTreeMap::new();
+    hashes.insert(
+        "pack.meta".to_owned(),
+        ledger::hash_reader(meta.as_bytes()).ok()?,
+    );
+    for (i, hash) in files {
+        a.append_path_with_name(data_path.join(i), format!("assets/{}", i))
+            .ok()?;
+        hashes.insert(format!("assets/{}", i), hash.to_owned());
+    }
+    append_manifest(&mut a, &hashes, key)?;
+
+    a.into_inner().ok()?.flush().ok()
+}
+
+/// Add a manifest of `hashes` to the end of a pack, and its signature if there is a key
+pub(super) fn append_manifest<W: Write>(
+    a: &mut tar::Builder<W>,
+    hashes: &BTreeMap<String, String>,
+    key: Option<&Keypair>,
+) -> Option<()> {
+    let manifest = manifest::write(hashes);
+    append_file(a, MANIFEST_FILE, manifest.as_bytes())?;
+    match key {
+        Some(key) => append_file(
+            a,
+            SIGNATURE_FILE,
+            signature::write(key, &manifest).as_bytes(),
+        ),
+        None => Some(()),
+    }
+}
+
+/// Get the assets in the data folder that were changed or added since `default.tar` was made,
+/// with the hash of each
+fn changed_files(path: &Path, game_path: &Path) -> Option<BTreeMap<String, String>> {
+    let default = load_default(path)
+        .ok()?
+        .hashes()?
+        .into_iter()
+        .filter_map(|(name, hash)| Some((ledger::asset_key(asset_path(&name)?), hash)))
+        .collect::<BTreeMap<_, _>>();
+    let ledger = Ledger::load(path, game_path);
+    let data_path = game_path.join("data");
+
+    let mut out = BTreeMap::new();
+    for i in list_files(&data_path).ok()? {
+        let key = ledger::asset_key(&i);
+        let hash = ledger::hash_file(&data_path.join(&i))?;
+        let installed = ledger.files.get(&key).and_then(|x| x.last());
+        if default.get(&key) == Some(&hash) || installed.map(|x| &x.hash) == Some(&hash) {
+            continue;
+        }
+
+        out.insert(key, hash);
+    }
+
+    Some(out)
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use super::ResourcePack;
+use crate::version::{Version, VersionRange};
+
+/// Another pack named in `requires` or `conflicts`, like `Base Fonts (1.2+)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackRef {
+    pub name: String,
+    pub versions: VersionRange,
+}
+
+impl PackRef {
+    /// Parse a comma separated list of pack names, each with an optional version range in brackets
+    pub fn parse_list(text: &str) -> Result<Vec<PackRef>, String> {
+        text.split(',')
+            .filter(|x| !x.trim().is_empty())
+            .map(PackRef::parse)
+            .collect()
+    }
+
+    pub fn parse(text: &str) -> Result<PackRef, String> {
+        let text = text.trim();
+        let (name, versions) = match text.split_once('(') {
+            Some((name, range)) => {
+                let range = range
+                    .strip_suffix(')')
+                    .ok_or_else(|| format!("`{}` is missing a `)`", text))?;
+                let versions = VersionRange::parse(range)
+                    .ok_or_else(|| format!("`{}` is not a range like `1.0 - 1.2`", range))?;
+                (name.trim(), versions)
+            }
+            None => (text, VersionRange::Any),
+        };
+
+        if name.is_empty() {
+            return Err(format!("`{}` is missing a pack name", text));
+        }
+
+        Ok(PackRef {
+            name: name.to_owned(),
+            versions,
+        })
+    }
+
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name.trim())
+    }
+
+    /// Check if a pack has this name and a version in range.
+    /// Packs with versions that can't be parsed only match `*`.
+    pub fn matches(&self, pack: &ResourcePack) -> bool {
+        self.matches_version(&pack.name, &pack.version)
+    }
+
+    pub fn matches_version(&self, name: &str, version: &str) -> bool {
+        if !self.is_named(name) {
+            return false;
+        }
+
+        match Version::parse(version) {
+            Some(i) => self.versions.contains(&i),
+            None => self.versions == VersionRange::Any,
+        }
+    }
+}
+
+impl fmt::Display for PackRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.versions {
+            VersionRange::Any => f.write_str(&self.name),
+            _ => write!(f, "{} ({})", self.name, self.versions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_refs() {
+        let refs = PackRef::parse_list("Base Fonts (1.2+), UI ,Sounds (1 - 2)").unwrap();
+        let text = refs.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        assert_eq!(text, vec!["Base Fonts (1.2+)", "UI", "Sounds (1 - 2)"]);
+        assert!(PackRef::parse_list(" , ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn invalid_refs() {
+        assert!(PackRef::parse("(1.2)").is_err());
+        assert!(PackRef::parse("Fonts (1.2").is_err());
+        assert!(PackRef::parse("Fonts (soon)").is_err());
+        assert!(PackRef::parse_list("UI, Fonts (2 - 1)").is_err());
+    }
+
+    #[test]
+    fn match_versions() {
+        let fonts = PackRef::parse("base fonts (1.2+)").unwrap();
+        assert!(fonts.matches_version("Base Fonts", "1.3"));
+        assert!(!fonts.matches_version("Base Fonts", "1.1"));
+        assert!(!fonts.matches_version("Fonts", "1.3"));
+        // Versions that can't be parsed only match `*`
+        assert!(!fonts.matches_version("Base Fonts", "beta"));
+        assert!(PackRef::parse("Base Fonts")
+            .unwrap()
+            .matches_version("Base Fonts", "beta"));
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use image::io::Reader as ImageReader;
+use serde::Serialize;
+
+use super::{is_image, ResourcePack};
+use crate::ledger;
+
+/// What a pack changes compared to the original game resources
+#[derive(Debug, Default, Serialize)]
+pub struct PackDiff {
+    /// Game assets the pack replaces with different files
+    pub overrides: Vec<Override>,
+    /// Assets the pack adds that the game doesn't have
+    pub additions: Vec<String>,
+    /// Game assets the pack leaves alone, or ships unchanged
+    pub untouched: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Override {
+    pub asset: String,
+    /// Image size in the game, if the asset is an image
+    pub original_size: Option<(u32, u32)>,
+    /// Image size in the pack, if the asset is an image
+    pub new_size: Option<(u32, u32)>,
+}
+
+struct FileInfo {
+    hash: String,
+    size: Option<(u32, u32)>,
+}
+
+impl ResourcePack {
+    /// Compare this pack's assets with `default`, normally the pack made from `default.tar`
+    pub fn diff(&self, default: &ResourcePack) -> Option<PackDiff> {
+        let original = scan(default)?;
+        let new = scan(self)?;
+        let mut diff = PackDiff::default();
+
+        for (asset, file) in &new {
+            match original.get(asset) {
+                None => diff.additions.push(asset.to_owned()),
+                Some(i) if i.hash == file.hash => diff.untouched.push(asset.to_owned()),
+                Some(i) => diff.overrides.push(Override {
+                    asset: asset.to_owned(),
+                    original_size: i.size,
+                    new_size: file.size,
+                }),
+            }
+        }
+
+        diff.untouched.extend(
+            original
+                .keys()
+                .filter(|x| !new.contains_key(*x))
+                .map(|x| x.to_owned()),
+        );
+        diff.untouched.sort();
+
+        Some(diff)
+    }
+}
+
+impl PackDiff {
+    /// Write the diff as JSON, for tools that want the structured data
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(i) = path.parent() {
+            fs::create_dir_all(i).map_err(|e| e.to_string())?;
+        }
+
+        let data = serde_json::to_vec_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, data).map_err(|e| e.to_string())
+    }
+}
+
+impl Override {
+    /// True if the asset is an image that changed size
+    pub fn resized(&self) -> bool {
+        self.original_size.is_some()
+            && self.new_size.is_some()
+            && self.original_size != self.new_size
+    }
+}
+
+/// Hash every asset in a pack and read the size of any images
+fn scan(pack: &ResourcePack) -> Option<BTreeMap<String, FileInfo>> {
+    let mut out = BTreeMap::new();
+
+    pack.for_each_file(|file, reader| {
+        let asset = match file.asset() {
+            Some(i) => ledger::asset_key(i),
+            None => return Some(()),
+        };
+
+        // Images are small enough to read whole, everything else is just hashed
+        let info = if is_image(&file.name) {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).ok()?;
+            FileInfo {
+                hash: ledger::hash_reader(data.as_slice()).ok()?,
+                size: ImageReader::new(Cursor::new(&data))
+                    .with_guessed_format()
+                    .ok()?
+                    .into_dimensions()
+                    .ok(),
+            }
+        } else {
+            FileInfo {
+                hash: ledger::hash_reader(reader).ok()?,
+                size: None,
+            }
+        };
+
+        out.insert(asset, info);
+        Some(())
+    })?;
+
+    Some(out)
+}
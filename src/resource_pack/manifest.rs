@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{ResourcePack, SIGNATURE_FILE};
+use crate::ledger;
+
+/// Name of the manifest file, next to `pack.meta`
+pub const MANIFEST_FILE: &str = "manifest";
+
+/// Files that don't match a pack's manifest
+#[derive(Debug, Default, Clone)]
+pub struct Verification {
+    /// Files in the manifest that aren't in the pack
+    pub missing: Vec<String>,
+    /// Files in the pack that aren't in the manifest
+    pub extra: Vec<String>,
+    /// Files whose contents don't match their hash
+    pub corrupted: Vec<String>,
+}
+
+impl ResourcePack {
+    /// Check every file in the pack against its manifest.
+    /// Packs without a manifest always pass. Returns None if the pack can't be read.
+    pub fn verify(&self) -> Option<Verification> {
+        if self.manifest.is_none() {
+            return Some(Verification::default());
+        }
+
+        let mut hashes = BTreeMap::new();
+        self.walk_files(true, |file, reader| {
+            hashes.insert(file.name.to_owned(), ledger::hash_reader(reader).ok()?);
+            Some(())
+        })?;
+
+        Some(self.verify_hashes(&hashes))
+    }
+
+    /// Check the hashes of the archive's own files, like those from `for_each_hashed`, against the manifest
+    pub fn verify_hashes(&self, hashes: &BTreeMap<String, String>) -> Verification {
+        let manifest = match &self.manifest {
+            Some(i) => i,
+            None => return Verification::default(),
+        };
+
+        let mut out = Verification::default();
+        for (name, hash) in manifest {
+            match hashes.get(name) {
+                None => out.missing.push(name.to_owned()),
+                Some(i) if i != hash => out.corrupted.push(name.to_owned()),
+                Some(_) => {}
+            }
+        }
+
+        out.extra = hashes
+            .keys()
+            .filter(|x| !manifest.contains_key(*x) && !is_unlisted(x))
+            .cloned()
+            .collect();
+
+        out
+    }
+}
+
+impl Verification {
+    pub fn passed(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.corrupted.is_empty()
+    }
+}
+
+/// Files that can't be listed in the manifest, as they are made after it
+pub fn is_unlisted(name: &str) -> bool {
+    name == MANIFEST_FILE || name == SIGNATURE_FILE
+}
+
+/// Parse a manifest made by `sha256sum`, one `<hash>  <file>` per line
+pub fn parse(text: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut out = BTreeMap::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (hash, name) = line
+            .split_once(char::is_whitespace)
+            .filter(|(hash, _)| hash.len() == 64 && hash.chars().all(|x| x.is_ascii_hexdigit()))
+            .ok_or_else(|| format!("manifest line {} is not `<sha256>  <file>`", i + 1))?;
+        let name = name.trim_start().trim_start_matches('*').replace('\\', "/");
+        let name = name.trim_start_matches("./");
+        if name.is_empty() {
+            return Err(format!("manifest line {} has no file name", i + 1));
+        }
+
+        if out.insert(name.to_owned(), hash.to_lowercase()).is_some() {
+            return Err(format!("manifest line {} lists {} again", i + 1, name));
+        }
+    }
+
+    Ok(out)
+}
+
+/// Write a manifest in the same format `parse` reads
+pub fn write(hashes: &BTreeMap<String, String>) -> String {
+    hashes
+        .iter()
+        .filter(|x| !is_unlisted(x.0))
+        .map(|(name, hash)| format!("{}  {}\n", hash, name))
+        .collect()
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.passed() {
+            return f.write_str("All files match the manifest");
+        }
+
+        let mut parts = Vec::new();
+        for (kind, files) in [
+            ("missing", &self.missing),
+            ("extra", &self.extra),
+            ("corrupted", &self.corrupted),
+        ] {
+            if !files.is_empty() {
+                parts.push(format!("{} {} ({})", files.len(), kind, files.join(", ")));
+            }
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &str = "ca978112ca1bbdcafac231b39a23dc4da786eff8146d8e7a3da35a3a1d7e9b7a";
+    const B: &str = "3E23E8160039594A33894F6564E1B1348BBD7A0088D42C4ACB73EEAED59C009D";
+
+    #[test]
+    fn parse_lines() {
+        let text = format!(
+            "# made by sha256sum\n\n{}  pack.meta\r\n{} *assets/a.png\n{}  ./assets\\b.png\n",
+            A, B, A
+        );
+        let out = parse(&text).unwrap();
+        assert_eq!(out.len(), 3);
+        assert_eq!(out["pack.meta"], A);
+        assert_eq!(out["assets/a.png"], B.to_lowercase());
+        assert_eq!(out["assets/b.png"], A);
+    }
+
+    #[test]
+    fn bad_hex() {
+        let hash = A.replace('c', "g");
+        let e = parse(&format!("{}  pack.meta\n", hash)).unwrap_err();
+        assert_eq!(e, "manifest line 1 is not `<sha256>  <file>`");
+    }
+
+    #[test]
+    fn wrong_length() {
+        for hash in [&A[1..], &format!("{}0", A), ""] {
+            let text = format!("{}  pack.meta\n{}  assets/a.png\n", A, hash);
+            assert_eq!(
+                parse(&text).unwrap_err(),
+                "manifest line 2 is not `<sha256>  <file>`",
+                "{}",
+                hash
+            );
+        }
+        assert!(parse(&format!("{}\n", A)).is_err());
+        assert!(parse(&format!("{}  *\n", A)).is_err());
+    }
+
+    #[test]
+    fn duplicate_paths() {
+        let text = format!("{}  assets/a.png\n{}  assets/a.png\n", A, A);
+        assert_eq!(
+            parse(&text).unwrap_err(),
+            "manifest line 2 lists assets/a.png again"
+        );
+
+        // The same file written differently
+        let text = format!("{}  assets/a.png\n{} *./assets\\a.png\n", A, B);
+        assert!(parse(&text).is_err());
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let mut hashes = BTreeMap::new();
+        hashes.insert("pack.meta".to_owned(), A.to_owned());
+        hashes.insert("assets/a.png".to_owned(), B.to_lowercase());
+        hashes.insert(MANIFEST_FILE.to_owned(), A.to_owned());
+        hashes.insert(SIGNATURE_FILE.to_owned(), A.to_owned());
+
+        let out = parse(&write(&hashes)).unwrap();
+        assert_eq!(out.len(), 2);
+        assert_eq!(out["assets/a.png"], hashes["assets/a.png"]);
+    }
+}
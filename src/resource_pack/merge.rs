@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use ed25519_dalek::Keypair;
+
+use super::create::{
+    append_file, append_manifest, append_reader, check_names, new_pack_file, signing_key,
+};
+use super::ResourcePack;
+use crate::ledger;
+
+/// Merge packs into one new pack in the resource_packs folder, crediting every source pack.
+/// Packs are ordered by priority, the first pack wins when two provide the same asset.
+/// With `sign` set the manifest is signed with the user's key, which is made if it is missing.
+/// Returns the new pack's file name and how many assets are in it, or why it couldn't be made.
+pub fn merge_packs(
+    path: &Path,
+    packs: &[ResourcePack],
+    name: &str,
+    author: &str,
+    sign: bool,
+) -> Result<(String, usize), String> {
+    let (name, author) = (name.trim(), author.trim());
+    if packs.is_empty() {
+        return Err("There are no packs to merge".to_owned());
+    }
+    check_names(name, author)?;
+
+    // Index of the highest priority pack with each asset
+    let mut winners = BTreeMap::new();
+    for (i, pack) in packs.iter().enumerate().rev() {
+        for j in pack.files.iter().filter(|x| x.asset().is_some()) {
+            winners.insert(j.name.to_owned(), i);
+        }
+    }
+
+    let meta = merged_meta(packs, name, author);
+    let key = signing_key(path, sign)?;
+    let (file, to, out) = new_pack_file(path, name, author)?;
+    if write_merged(out, &meta, packs, &winners, key.as_ref()).is_none() {
+        fs::remove_file(&to).unwrap_or_default();
+        return Err(format!("Failed to write {}", file));
+    }
+
+    Ok((file, winners.len()))
+}
+
+/// Make the `pack.meta` for a merged pack.
+/// Requirements the merged packs don't fill between them are kept.
+fn merged_meta(packs: &[ResourcePack], name: &str, author: &str) -> String {
+    let credits = packs
+        .iter()
+        .map(|x| format!("{} {} by {}", x.name, x.version, x.author))
+        .collect::<Vec<_>>();
+    let mut requires = Vec::new();
+    let mut conflicts = Vec::new();
+    for i in packs {
+        for j in &i.requires {
+            let filled = packs.iter().any(|x| j.matches(x));
+            if !filled && !requires.contains(&j.to_string()) {
+                requires.push(j.to_string());
+            }
+        }
+        for j in &i.conflicts {
+            if !conflicts.contains(&j.to_string()) {
+                conflicts.push(j.to_string());
+            }
+        }
+    }
+
+    let mut meta = format!(
+        "; Generated by Freeways-Launcher by merging {} packs\nname = {}\nauthor = {}\nversion = 1.0\n",
+        packs.len(),
+        name,
+        author
+    );
+    meta.push_str(&format!(
+        "description = Merged from {}\ncredits = {}\n",
+        packs
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", "),
+        credits.join(", ")
+    ));
+    if !requires.is_empty() {
+        meta.push_str(&format!("requires = {}\n", requires.join(", ")));
+    }
+    if !conflicts.is_empty() {
+        meta.push_str(&format!("conflicts = {}\n", conflicts.join(", ")));
+    }
+
+    meta
+}
+
+/// Stream the winning assets from every pack into the new pack, with a manifest signed if there is a key
+fn write_merged(
+    out: File,
+    meta: &str,
+    packs: &[ResourcePack],
+    winners: &BTreeMap<String, usize>,
+    key: Option<&Keypair>,
+) -> Option<()> {
+    let mut hashes = BTreeMap::new();
+    hashes.insert(
+        "pack.meta".to_owned(),
+        ledger::hash_reader(meta.as_bytes()).ok()?,
+    );
+    for (i, pack) in packs.iter().enumerate() {
+        for (name, hash) in pack.hashes()? {
+            if winners.get(&name) == Some(&i) {
+                hashes.insert(name, hash);
+            }
+        }
+    }
+
+    let mut a = tar::Builder::new(out);
+    append_file(&mut a, "pack.meta", meta.as_bytes())?;
+    for (i, pack) in packs.iter().enumerate() {
+        pack.for_each_file(|file, reader| {
+            if winners.get(&file.name) == Some(&i) {
+                append_reader(&mut a, &file.name, file.size, reader)?;
+            }
+            Some(())
+        })?;
+    }
+    append_manifest(&mut a, &hashes, key)?;
+
+    a.into_inner().ok()?.flush().ok()
+}
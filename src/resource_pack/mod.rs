@@ -0,0 +1,670 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::io::Read;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::path::{Component, Path};
+
+use simple_config_parser::config::Config;
+use zip::result::ZipError;
+
+use crate::install::Transaction;
+use crate::ledger::{self, HashReader};
+use crate::version::VersionRange;
+
+mod archive;
+mod compress;
+mod create;
+mod depends;
+mod diff;
+mod manifest;
+mod merge;
+mod preview;
+mod signature;
+mod validate;
+
+pub use archive::PackFormat;
+pub use compress::Compression;
+pub use create::{append_file, create_pack};
+pub use depends::PackRef;
+pub use diff::PackDiff;
+pub use manifest::MANIFEST_FILE;
+pub use merge::merge_packs;
+pub use preview::{PREVIEW_HEIGHT, THUMBNAIL_SIZE};
+pub use signature::{PackSignature, SignatureStatus, SIGNATURE_FILE};
+
+/// Newest `pack_format` this launcher can read
+pub const PACK_FORMAT: u32 = 1;
+
+/// A pack on disk. Only the file index and `pack.meta` are read when it is loaded,
+/// file contents are streamed from the archive when they are needed.
+pub struct ResourcePack {
+    pub name: String,
+    pub author: String,
+    pub version: String,
+
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub license: Option<String>,
+    /// Game versions the pack was made for
+    pub game_version: Option<VersionRange>,
+    /// Version of the pack layout, see [`PACK_FORMAT`]
+    pub pack_format: Option<u32>,
+    /// Path of the pack's icon, relative to the pack root
+    pub icon: Option<String>,
+    /// Paths of preview screenshots, relative to the pack root
+    pub previews: Vec<String>,
+    /// Packs that must be installed under this one
+    pub requires: Vec<PackRef>,
+    /// Packs that can't be enabled with this one
+    pub conflicts: Vec<PackRef>,
+    /// Hash of every file, from the pack's optional manifest
+    pub manifest: Option<BTreeMap<String, String>>,
+    /// The author's signature of the manifest
+    pub signature: Option<PackSignature>,
+
+    pub path: PathBuf,
+    pub format: PackFormat,
+    pub files: Vec<RawFile>,
+
+    /// Folder inside the archive that holds `pack.meta`
+    root: String,
+    /// Files replaced in memory, like images resized by `fix_images`
+    fixed: BTreeMap<String, FixedFile>,
+}
+
+/// A file replaced in memory, with the hash of the archive's copy it was made from
+#[derive(Debug)]
+struct FixedFile {
+    source_hash: String,
+    data: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct RawFile {
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Archive(String),
+    UnknownFormat,
+    UnsafeEntry { entry: String, reason: &'static str },
+    NoMeta,
+    InvalidMeta(String),
+}
+
+impl ResourcePack {
+    pub fn load(path: PathBuf) -> Result<ResourcePack, LoadError> {
+        let format = PackFormat::detect(&path).ok_or(LoadError::UnknownFormat)?;
+
+        // Index the pack, only keeping the contents of pack.meta files
+        let mut index = Vec::new();
+        let mut metas = Vec::new();
+        let _ = archive::walk(&path, format, &mut |name, size, reader| {
+            if is_meta(name) {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                metas.push((name.to_owned(), data));
+            }
+            index.push((name.to_owned(), size));
+            Ok(ControlFlow::Continue(()))
+        })?;
+
+        let root = find_root(metas.iter().map(|x| x.0.as_str()).filter(|x| is_meta(x)))?;
+        let files = index
+            .into_iter()
+            .filter_map(|(name, size)| {
+                Some(RawFile {
+                    name: strip_root(&root, &name)?,
+                    size,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Get Pack Meta
+        let meta = metas
+            .iter()
+            .find(|x| strip_root(&root, &x.0).as_deref() == Some("pack.meta"))
+            .unwrap();
+
+        // Only the manifest and signature next to pack.meta count, any deeper are just files
+        let wanted = [MANIFEST_FILE, SIGNATURE_FILE]
+            .into_iter()
+            .filter(|x| files.iter().any(|y| y.name == *x))
+            .collect::<Vec<_>>();
+        let mut extra = BTreeMap::new();
+        if !wanted.is_empty() {
+            let _ = archive::walk(&path, format, &mut |name, _, reader| {
+                if let Some(i) = strip_root(&root, name).filter(|x| wanted.contains(&x.as_str())) {
+                    let mut data = Vec::new();
+                    reader.read_to_end(&mut data)?;
+                    extra.insert(i, data);
+                }
+
+                if extra.len() == wanted.len() {
+                    Ok(ControlFlow::Break(()))
+                } else {
+                    Ok(ControlFlow::Continue(()))
+                }
+            })?;
+        }
+        let manifest = extra
+            .get(MANIFEST_FILE)
+            .map(|x| manifest::parse(&String::from_utf8_lossy(x)))
+            .transpose()
+            .map_err(LoadError::InvalidMeta)?;
+        let signature = extra
+            .get(SIGNATURE_FILE)
+            .map(|x| signature::parse(&String::from_utf8_lossy(x)))
+            .transpose()
+            .map_err(LoadError::InvalidMeta)?;
+        let mut conf = Config::new(None);
+        conf.parse(&String::from_utf8_lossy(&meta.1).replace('\r', ""))
+            .map_err(|_| LoadError::InvalidMeta("pack.meta is not a valid config".to_owned()))?;
+        let get = |key| {
+            conf.get(key)
+                .ok_or_else(|| LoadError::InvalidMeta(format!("pack.meta is missing `{}`", key)))
+        };
+        let optional = |key| conf.get(key).filter(|x: &String| !x.trim().is_empty());
+        let invalid = |key, value: &str, why| {
+            LoadError::InvalidMeta(format!("pack.meta `{}` {}: '{}'", key, why, value))
+        };
+
+        let homepage = optional("homepage");
+        if let Some(i) = &homepage {
+            if !i.starts_with("http://") && !i.starts_with("https://") {
+                return Err(invalid("homepage", i, "must be an http or https link"));
+            }
+        }
+
+        let game_version = optional("game_version")
+            .map(|x| {
+                VersionRange::parse(&x)
+                    .ok_or_else(|| invalid("game_version", &x, "is not a range like `1.0 - 1.2`"))
+            })
+            .transpose()?;
+
+        let pack_format = optional("pack_format")
+            .map(|x| match x.trim().parse::<u32>() {
+                Ok(i) if i > PACK_FORMAT => Err(invalid(
+                    "pack_format",
+                    &x,
+                    "is newer than this launcher supports",
+                )),
+                Ok(i) => Ok(i),
+                Err(_) => Err(invalid("pack_format", &x, "is not a whole number")),
+            })
+            .transpose()?;
+
+        let pack_file = |key, x: &str| {
+            let name = x
+                .trim()
+                .replace('\\', "/")
+                .trim_start_matches("./")
+                .to_owned();
+            if files.iter().any(|x| x.name == name) {
+                Ok(name)
+            } else {
+                Err(invalid(key, x, "is not a file in the pack"))
+            }
+        };
+        let icon = optional("icon")
+            .map(|x| pack_file("icon", &x))
+            .transpose()?;
+        let previews = optional("previews")
+            .map(|x| {
+                x.split(',')
+                    .filter(|x| !x.trim().is_empty())
+                    .map(|x| pack_file("previews", x))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let pack_refs = |key| {
+            optional(key)
+                .map(|x| {
+                    PackRef::parse_list(&x).map_err(|e| {
+                        LoadError::InvalidMeta(format!(
+                            "pack.meta `{}` has an invalid pack: {}",
+                            key, e
+                        ))
+                    })
+                })
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+        let requires = pack_refs("requires")?;
+        let conflicts = pack_refs("conflicts")?;
+
+        Ok(ResourcePack {
+            name: get("name")?,
+            author: get("author")?,
+            version: get("version")?,
+            description: optional("description"),
+            homepage,
+            license: optional("license"),
+            game_version,
+            pack_format,
+            icon,
+            previews,
+            requires,
+            conflicts,
+            manifest,
+            signature,
+            path,
+            format,
+            files,
+            root,
+            fixed: BTreeMap::new(),
+        })
+    }
+
+    /// Stream every file in the pack to `f`, one at a time.
+    /// Stops early if `f` returns None.
+    pub fn for_each_file(
+        &self,
+        f: impl FnMut(&RawFile, &mut dyn Read) -> Option<()>,
+    ) -> Option<()> {
+        self.walk_files(false, f)
+    }
+
+    /// Like `for_each_file`, but with `raw` set files replaced in memory are read from the archive
+    fn walk_files(
+        &self,
+        raw: bool,
+        mut f: impl FnMut(&RawFile, &mut dyn Read) -> Option<()>,
+    ) -> Option<()> {
+        let out = archive::walk(&self.path, self.format, &mut |name, size, reader| {
+            let name = match strip_root(&self.root, name) {
+                Some(i) => i,
+                None => return Ok(ControlFlow::Continue(())),
+            };
+
+            let fixed = if raw { None } else { self.fixed.get(&name) };
+            let out = match fixed {
+                Some(i) => f(
+                    &RawFile {
+                        name,
+                        size: i.data.len() as u64,
+                    },
+                    &mut i.data.as_slice(),
+                ),
+                None => f(&RawFile { name, size }, reader),
+            };
+            match out {
+                Some(_) => Ok(ControlFlow::Continue(())),
+                None => Ok(ControlFlow::Break(())),
+            }
+        });
+
+        match out {
+            Ok(ControlFlow::Continue(_)) => Some(()),
+            Ok(ControlFlow::Break(_)) => None,
+            Err(e) => {
+                println!("[!] Failed to read '{}': {}", self.name, e);
+                None
+            }
+        }
+    }
+
+    /// Stream every file in the pack to `f` like `for_each_file`, in a single pass that also
+    /// hashes the archive's own copy of each file. Returns those hashes, so the pack can be
+    /// verified against the same bytes that were passed on.
+    /// Files replaced in memory are only used if the archive's copy is still the one they were made from.
+    pub fn for_each_hashed(
+        &self,
+        mut f: impl FnMut(&RawFile, &mut dyn Read) -> Option<()>,
+    ) -> Option<BTreeMap<String, String>> {
+        let mut out = BTreeMap::new();
+        self.walk_files(true, |file, reader| {
+            let mut reader = HashReader::new(reader);
+            match self.fixed.get(&file.name) {
+                Some(i) => {
+                    io::copy(&mut reader, &mut io::sink()).ok()?;
+                    if reader.hash() != i.source_hash {
+                        println!("[!] {} changed since it was fixed", file.name);
+                        return None;
+                    }
+                    let fixed = RawFile {
+                        name: file.name.to_owned(),
+                        size: i.data.len() as u64,
+                    };
+                    f(&fixed, &mut i.data.as_slice())?;
+                }
+                None => f(file, &mut reader)?,
+            }
+
+            out.insert(file.name.to_owned(), reader.finish().ok()?);
+            Some(())
+        })?;
+
+        Some(out)
+    }
+
+    /// Hash of every file in the pack, by name
+    pub fn hashes(&self) -> Option<BTreeMap<String, String>> {
+        let mut out = BTreeMap::new();
+        self.for_each_file(|file, reader| {
+            out.insert(file.name.to_owned(), ledger::hash_reader(reader).ok()?);
+            Some(())
+        })?;
+
+        Some(out)
+    }
+
+    /// Make the game's data folder match this pack exactly.
+    /// Assets are rewritten where they differ and files the pack doesn't have are removed.
+    pub fn restore(&self, path: &Path, game_path: &Path) -> Option<()> {
+        let data_path = game_path.join("data");
+        let mut transaction = Transaction::new(path.join("backup"))?;
+
+        match restore_files(&mut transaction, &data_path, self) {
+            Some(_) => transaction.commit(),
+            None => {
+                println!("[!] Failed to restore resources, rolling back");
+                transaction.rollback();
+                return None;
+            }
+        }
+
+        Some(())
+    }
+}
+
+impl RawFile {
+    /// Path of the file inside the game's data folder, if it is an asset
+    pub fn asset(&self) -> Option<&Path> {
+        asset_path(&self.name)
+    }
+}
+
+/// Path of a pack file inside the game's data folder, if it is an asset
+pub fn asset_path(name: &str) -> Option<&Path> {
+    match Path::new(name).strip_prefix("assets") {
+        Ok(i) if !i.as_os_str().is_empty() => Some(i),
+        _ => None,
+    }
+}
+
+/// True if the file is an image the launcher can decode
+pub fn is_image(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.ends_with(".png") || name.ends_with(".jpg") || name.ends_with(".jpeg")
+}
+
+/// Load the snapshot of the original game resources
+pub fn load_default(path: &Path) -> Result<ResourcePack, LoadError> {
+    ResourcePack::load(path.join("resource_packs").join("default.tar"))
+}
+
+/// Put the game's data folder back to how it was when `default.tar` was made.
+/// This also removes the game's pack ledger, and the stored originals once no ledger needs them.
+pub fn restore_default(path: &Path, game_path: &Path) -> Option<()> {
+    let default = load_default(path).ok()?;
+    default.restore(path, game_path)?;
+
+    let ledger = ledger::ledger_path(path, game_path);
+    if ledger.exists() {
+        fs::remove_file(&ledger).ok()?;
+    }
+    if fs::remove_dir(path.join(ledger::LEDGER_FOLDER)).is_ok()
+        || !path.join(ledger::LEDGER_FOLDER).exists()
+    {
+        fs::remove_dir_all(path.join("originals")).unwrap_or_default();
+    }
+    Some(())
+}
+
+fn restore_files(
+    transaction: &mut Transaction,
+    data_path: &Path,
+    pack: &ResourcePack,
+) -> Option<()> {
+    let hashes = pack.hashes()?;
+    let assets = hashes
+        .iter()
+        .filter_map(|(name, hash)| Some((asset_path(name)?.to_path_buf(), hash)))
+        .collect::<BTreeMap<_, _>>();
+
+    for i in list_files(data_path).ok()? {
+        if assets.contains_key(&i) {
+            continue;
+        }
+
+        transaction.remove(&data_path.join(&i))?;
+        transaction.remove_empty_dirs(data_path, &data_path.join(&i))?;
+    }
+
+    // Only stream the files that are different
+    let write = assets
+        .into_iter()
+        .filter(|(asset, hash)| ledger::hash_file(&data_path.join(asset)).as_ref() != Some(hash))
+        .map(|x| x.0)
+        .collect::<BTreeSet<_>>();
+
+    pack.for_each_file(|file, reader| match file.asset() {
+        Some(i) if write.contains(i) => transaction.write_from(&data_path.join(i), reader),
+        _ => Some(()),
+    })
+}
+
+/// Get every pack in the resource_packs folder.
+/// This is any archive we can read and any folder with a `pack.meta` in it.
+pub fn find_packs(path: &Path) -> Vec<PathBuf> {
+    let mut packs = match fs::read_dir(path) {
+        Ok(i) => i
+            .filter_map(|x| Some(x.ok()?.path()))
+            .filter(|x| match PackFormat::detect(x) {
+                Some(PackFormat::Dir) => x.join("pack.meta").exists(),
+                Some(_) => true,
+                None => false,
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => return Vec::new(),
+    };
+
+    packs.sort();
+    packs
+}
+
+/// Copy a pack into the resource_packs folder.
+/// Returns the name of the new pack, or why it couldn't be imported.
+pub fn import_pack(from: &Path, path: &Path) -> Result<String, String> {
+    ResourcePack::load(from.to_path_buf()).map_err(|e| e.to_string())?;
+
+    let name = match from.file_name() {
+        Some(i) => i.to_string_lossy().to_string(),
+        None => return Err("Invalid path".to_owned()),
+    };
+    let to = path.join(&name);
+
+    // Made with create_new so a pack imported at the same time is never overwritten
+    let made = if from.is_dir() {
+        fs::create_dir(&to).map(|_| None)
+    } else {
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&to)
+            .map(Some)
+    };
+    let copied = match made {
+        Ok(None) => copy_dir(from, &to),
+        Ok(Some(mut file)) => File::open(from)
+            .and_then(|mut x| io::copy(&mut x, &mut file))
+            .ok()
+            .map(|_| ()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(format!("A pack named {} already exists", name))
+        }
+        Err(e) => return Err(format!("Failed to copy {}: {}", name, e)),
+    };
+
+    if copied.is_none() {
+        delete_pack(&to).unwrap_or_default();
+        return Err(format!("Failed to copy {}", name));
+    }
+    Ok(name)
+}
+
+/// Remove a pack from the resource_packs folder
+pub fn delete_pack(path: &Path) -> Option<()> {
+    if path.is_dir() {
+        return fs::remove_dir_all(path).ok();
+    }
+
+    fs::remove_file(path).ok()
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Option<()> {
+    fs::create_dir_all(to).ok()?;
+
+    for i in fs::read_dir(from).ok()? {
+        let i = i.ok()?.path();
+        let to = to.join(i.file_name()?);
+
+        if i.is_dir() {
+            copy_dir(&i, &to)?;
+            continue;
+        }
+
+        fs::copy(&i, to).ok()?;
+    }
+
+    Some(())
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "{}", e),
+            LoadError::Archive(e) => write!(f, "Invalid archive: {}", e),
+            LoadError::UnknownFormat => {
+                f.write_str("Not a .zip, .tar, .tar.gz, .tar.xz, .tar.zst or pack folder")
+            }
+            LoadError::UnsafeEntry { entry, reason } => {
+                write!(f, "Unsafe entry '{}': {}", entry, reason)
+            }
+            LoadError::NoMeta => f.write_str("No pack.meta found"),
+            LoadError::InvalidMeta(e) => f.write_str(e),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<ZipError> for LoadError {
+    fn from(e: ZipError) -> Self {
+        match e {
+            ZipError::Io(e) => LoadError::Io(e),
+            e => LoadError::Archive(e.to_string()),
+        }
+    }
+}
+
+impl fmt::Debug for ResourcePack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResourcePack")
+            .field("name", &self.name)
+            .field("author", &self.author)
+            .field("version", &self.version)
+            .field("description", &self.description)
+            .field("homepage", &self.homepage)
+            .field("license", &self.license)
+            .field("game_version", &self.game_version)
+            .field("pack_format", &self.pack_format)
+            .field("icon", &self.icon)
+            .field("previews", &self.previews)
+            .field("manifest", &self.manifest.as_ref().map(|x| x.len()))
+            .field("signature", &self.signature.is_some())
+            .field("path", &self.path)
+            .field("files", &self.files.len())
+            .finish()
+    }
+}
+
+/// Get the path of every file in a folder, relative to that folder
+pub fn list_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    fn walk(root: &Path, path: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+        for i in fs::read_dir(path)? {
+            let i = i?;
+
+            // Don't follow links out of the folder
+            if i.file_type()?.is_dir() {
+                walk(root, &i.path(), out)?;
+                continue;
+            }
+
+            out.push(i.path().strip_prefix(root).unwrap().to_path_buf());
+        }
+
+        Ok(())
+    }
+
+    let mut out = Vec::new();
+    walk(path, path, &mut out)?;
+    Ok(out)
+}
+
+fn base_name(name: &str) -> String {
+    name.replace('\\', "/")
+        .rsplit('/')
+        .next()
+        .unwrap()
+        .to_owned()
+}
+
+fn is_meta(name: &str) -> bool {
+    base_name(name).to_lowercase() == "pack.meta"
+}
+
+/// Zip tools often wrap everything in a folder named after the pack.
+/// Use the folder holding the shallowest `pack.meta` as the root,
+/// refusing packs with more than one at that depth.
+fn find_root<'a>(metas: impl Iterator<Item = &'a str>) -> Result<String, LoadError> {
+    let roots = metas
+        .map(|x| {
+            let x = x.replace('\\', "/");
+            x[..x.len() - "pack.meta".len()].to_owned()
+        })
+        .collect::<BTreeSet<_>>();
+    let depth = |x: &String| {
+        Path::new(x)
+            .components()
+            .filter(|x| matches!(x, Component::Normal(_)))
+            .count()
+    };
+
+    let shallowest = roots.iter().map(depth).min().ok_or(LoadError::NoMeta)?;
+    let mut roots = roots.into_iter().filter(|x| depth(x) == shallowest);
+    let root = roots.next().ok_or(LoadError::NoMeta)?;
+    if let Some(other) = roots.next() {
+        return Err(LoadError::Archive(format!(
+            "there is a pack.meta in both '{}' and '{}'",
+            root, other
+        )));
+    }
+
+    Ok(root)
+}
+
+/// Name of a file relative to the pack root, or None if it is outside it
+fn strip_root(root: &str, name: &str) -> Option<String> {
+    let name = name.replace('\\', "/");
+    let name = name.strip_prefix(root)?.trim_start_matches("./");
+
+    if name.to_lowercase() == "pack.meta" {
+        return Some("pack.meta".to_owned());
+    }
+    Some(name.to_owned())
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+use super::{is_image, ResourcePack};
+
+/// Size of the square thumbnail shown next to each pack
+pub const THUMBNAIL_SIZE: u32 = 64;
+
+/// Height preview screenshots are scaled to
+pub const PREVIEW_HEIGHT: u32 = 64;
+
+/// Contact sheets are a square grid of this many assets across
+const CONTACT_SHEET_GRID: u32 = 2;
+
+impl ResourcePack {
+    /// Get a square thumbnail for the pack.
+    /// This is its icon, `pack.png` or its first preview, whichever is found first.
+    /// Packs with none of these get a contact sheet of their first few image assets.
+    pub fn thumbnail(&self) -> Option<RgbaImage> {
+        let icon = self
+            .icon
+            .as_deref()
+            .or_else(|| {
+                self.files
+                    .iter()
+                    .find(|x| x.name == "pack.png")
+                    .map(|x| x.name.as_str())
+            })
+            .or_else(|| self.previews.first().map(|x| x.as_str()));
+
+        if let Some(i) = icon.and_then(|x| self.read_images(&[x]).remove(x)) {
+            return Some(fit(&i, THUMBNAIL_SIZE, THUMBNAIL_SIZE));
+        }
+
+        self.contact_sheet()
+    }
+
+    /// Get the pack's preview screenshots, scaled to [`PREVIEW_HEIGHT`]
+    pub fn preview_images(&self) -> Vec<RgbaImage> {
+        let names = self.previews.iter().map(|x| x.as_str()).collect::<Vec<_>>();
+        let mut images = self.read_images(&names);
+
+        names
+            .iter()
+            .filter_map(|x| images.remove(*x))
+            .map(|x| {
+                let width = (x.width() * PREVIEW_HEIGHT / x.height().max(1)).max(1);
+                x.resize_exact(width, PREVIEW_HEIGHT, FilterType::Triangle)
+                    .to_rgba8()
+            })
+            .collect()
+    }
+
+    /// Tile the first few image assets into a thumbnail
+    fn contact_sheet(&self) -> Option<RgbaImage> {
+        let names = self
+            .files
+            .iter()
+            .filter(|x| x.asset().is_some() && is_image(&x.name))
+            .take((CONTACT_SHEET_GRID * CONTACT_SHEET_GRID) as usize)
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        let images = self.read_images(&names);
+        if images.is_empty() {
+            return None;
+        }
+
+        let cell = THUMBNAIL_SIZE / CONTACT_SHEET_GRID;
+        let mut out = RgbaImage::new(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+        for (i, image) in names.iter().filter_map(|x| images.get(*x)).enumerate() {
+            let (x, y) = (i as u32 % CONTACT_SHEET_GRID, i as u32 / CONTACT_SHEET_GRID);
+            imageops::overlay(&mut out, &fit(image, cell, cell), x * cell, y * cell);
+        }
+
+        Some(out)
+    }
+
+    /// Decode the named images in a single pass over the pack.
+    /// Files that aren't valid images are left out.
+    fn read_images(&self, names: &[&str]) -> BTreeMap<String, DynamicImage> {
+        let mut out = BTreeMap::new();
+        if names.is_empty() {
+            return out;
+        }
+
+        self.for_each_file(|file, reader| {
+            if names.contains(&file.name.as_str()) {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data).ok()?;
+                if let Ok(i) = image::load_from_memory(&data) {
+                    out.insert(file.name.to_owned(), i);
+                }
+            }
+            Some(())
+        });
+
+        out
+    }
+}
+
+/// Scale an image to fit in the given size, centred on a transparent background
+fn fit(image: &DynamicImage, width: u32, height: u32) -> RgbaImage {
+    let scaled = image.resize(width, height, FilterType::Triangle).to_rgba8();
+    let mut out = RgbaImage::new(width, height);
+    imageops::overlay(
+        &mut out,
+        &scaled,
+        (width - scaled.width()) / 2,
+        (height - scaled.height()) / 2,
+    );
+
+    out
+}
@@ -0,0 +1,122 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use simple_config_parser::config::Config;
+
+use super::manifest::{self, MANIFEST_FILE};
+use super::{PackFormat, ResourcePack};
+use crate::trust::TrustStore;
+
+/// Name of the signature file, next to `pack.meta`
+pub const SIGNATURE_FILE: &str = "signature";
+
+/// An author's signature of a pack's manifest
+#[derive(Debug, Clone)]
+pub struct PackSignature {
+    pub key: PublicKey,
+    pub signature: Signature,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Signed by a key in the trust store, with the name it was trusted under
+    Trusted(String),
+    /// Signed by a key that isn't in the trust store
+    Unknown(String),
+    Unsigned,
+    /// The signature doesn't match the pack
+    Invalid(String),
+}
+
+impl ResourcePack {
+    /// Check the pack's signature matches its manifest.
+    /// Returns the signing key, None for unsigned packs, or why the signature is invalid.
+    /// The manifest covers every file, so `verify` must also pass for the contents to be trusted.
+    pub fn check_signature(&self) -> Result<Option<&PublicKey>, String> {
+        let signature = match &self.signature {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+        let manifest = self
+            .manifest
+            .as_ref()
+            .ok_or("signed packs need a manifest")?;
+
+        signature
+            .key
+            .verify(manifest::write(manifest).as_bytes(), &signature.signature)
+            .map_err(|_| "the signature doesn't match the manifest".to_owned())?;
+
+        Ok(Some(&signature.key))
+    }
+
+    pub fn signature_status(&self, trust: &TrustStore) -> SignatureStatus {
+        match self.check_signature() {
+            Ok(Some(key)) => match trust.get(key) {
+                Some(i) => SignatureStatus::Trusted(i.name.to_owned()),
+                None => SignatureStatus::Unknown(hex::encode(key.to_bytes())),
+            },
+            Ok(None) => SignatureStatus::Unsigned,
+            Err(e) => SignatureStatus::Invalid(e),
+        }
+    }
+
+    /// Write a fresh manifest and signature into a folder pack.
+    /// Archives can't be changed in place, so sign the folder before packing it up.
+    pub fn sign(&self, key: &Keypair) -> Result<(), String> {
+        if self.format != PackFormat::Dir {
+            return Err("Only folder packs can be signed".to_owned());
+        }
+
+        let hashes = self
+            .hashes()
+            .ok_or_else(|| format!("Failed to read {}", self.name))?;
+        let manifest = manifest::write(&hashes);
+        let root = self.path.join(&self.root);
+
+        fs::write(root.join(MANIFEST_FILE), &manifest)
+            .and_then(|_| fs::write(root.join(SIGNATURE_FILE), write(key, &manifest)))
+            .map_err(|e| format!("Failed to write the signature: {}", e))
+    }
+}
+
+/// Parse a signature file, holding the author's public key and the signature in hex
+pub fn parse(text: &str) -> Result<PackSignature, String> {
+    let mut conf = Config::new(None);
+    conf.parse(&text.replace('\r', ""))
+        .map_err(|_| "signature is not a valid config".to_owned())?;
+    let get = |key| {
+        conf.get(key)
+            .and_then(|x| hex::decode(x.trim()).ok())
+            .ok_or_else(|| format!("signature `{}` is missing or not hex", key))
+    };
+
+    Ok(PackSignature {
+        key: PublicKey::from_bytes(&get("key")?)
+            .map_err(|_| "signature `key` is not an ed25519 public key".to_owned())?,
+        signature: Signature::try_from(get("signature")?.as_slice())
+            .map_err(|_| "signature `signature` is not an ed25519 signature".to_owned())?,
+    })
+}
+
+/// Sign a manifest, giving the contents of a signature file
+pub fn write(key: &Keypair, manifest: &str) -> String {
+    format!(
+        "; Pack signature, made by Freeways-Launcher\nkey = {}\nsignature = {}\n",
+        hex::encode(key.public.to_bytes()),
+        hex::encode(key.sign(manifest.as_bytes()).to_bytes())
+    )
+}
+
+impl fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureStatus::Trusted(i) => write!(f, "Signed by a trusted author ({})", i),
+            SignatureStatus::Unknown(i) => write!(f, "Signed by an unknown key ({})", &i[..16]),
+            SignatureStatus::Unsigned => f.write_str("Unsigned"),
+            SignatureStatus::Invalid(e) => write!(f, "Invalid signature: {}", e),
+        }
+    }
+}
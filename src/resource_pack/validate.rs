@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use image::imageops::FilterType;
+use image::{ColorType, DynamicImage, GenericImageView, ImageFormat};
+
+use super::{is_image, FixedFile, ResourcePack};
+use crate::ledger;
+
+/// An image asset that doesn't match the game's original
+#[derive(Debug, Clone)]
+pub struct ImageMismatch {
+    pub asset: String,
+    pub expected: ImageShape,
+    /// The pack's image, or None if it can't be decoded
+    pub found: Option<ImageShape>,
+    /// Why the image couldn't be decoded or resized
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageShape {
+    pub width: u32,
+    pub height: u32,
+    pub color: ColorType,
+}
+
+impl ResourcePack {
+    /// Compare every image asset with the same asset in `default`.
+    /// Images the game doesn't have are not checked, images that can't be decoded are reported.
+    /// Returns None if either pack can't be read.
+    pub fn check_images(&self, default: &ResourcePack) -> Option<Vec<ImageMismatch>> {
+        let wanted = self
+            .files
+            .iter()
+            .filter(|x| is_image(&x.name))
+            .filter_map(|x| Some(ledger::asset_key(x.asset()?)))
+            .collect::<BTreeSet<_>>();
+        let expected = shapes(default, &wanted)?
+            .into_iter()
+            .filter_map(|(asset, shape)| Some((asset, shape.ok()?)))
+            .collect::<BTreeMap<_, _>>();
+
+        let wanted = expected.keys().cloned().collect();
+        let found = shapes(self, &wanted)?;
+
+        Some(
+            found
+                .into_iter()
+                .filter_map(|(asset, found)| {
+                    let expected = expected[&asset];
+                    let (found, error) = match found {
+                        Ok(i) if i == expected => return None,
+                        Ok(i) => (Some(i), None),
+                        Err(e) => (None, Some(e)),
+                    };
+
+                    Some(ImageMismatch {
+                        asset,
+                        expected,
+                        found,
+                        error,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Resample any image that doesn't match `default` to the original size and colour format.
+    /// The fixed images are kept in memory and used in place of the pack's own.
+    /// An image that can't be resized is left as it is, with the reason in its mismatch.
+    /// Returns every mismatched image.
+    pub fn fix_images(&mut self, default: &ResourcePack) -> Option<Vec<ImageMismatch>> {
+        let mut mismatches = self.check_images(default)?;
+        let expected = mismatches
+            .iter()
+            .filter(|x| x.error.is_none())
+            .map(|x| (x.asset.to_owned(), x.expected))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut fixed = BTreeMap::new();
+        let mut failed = BTreeMap::new();
+        self.for_each_file(|file, reader| {
+            let asset = match file.asset().map(ledger::asset_key) {
+                Some(i) if expected.contains_key(&i) => i,
+                _ => return Some(()),
+            };
+
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data).ok()?;
+            let out = match resize(&data, &file.name, expected[&asset]) {
+                Ok(i) => i,
+                Err(e) => {
+                    failed.insert(asset, e);
+                    return Some(());
+                }
+            };
+            fixed.insert(
+                file.name.to_owned(),
+                FixedFile {
+                    source_hash: ledger::hash_reader(data.as_slice()).ok()?,
+                    data: out,
+                },
+            );
+            Some(())
+        })?;
+
+        for i in self.files.iter_mut() {
+            if let Some(fixed) = fixed.get(&i.name) {
+                i.size = fixed.data.len() as u64;
+            }
+        }
+        self.fixed.extend(fixed);
+
+        for i in mismatches.iter_mut() {
+            if let Some(e) = failed.remove(&i.asset) {
+                i.error = Some(e);
+            }
+        }
+        Some(mismatches)
+    }
+}
+
+/// Resample an image to a shape, keeping its file format
+fn resize(data: &[u8], name: &str, shape: ImageShape) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory(data).map_err(|e| format!("can't be decoded: {}", e))?;
+    let image = convert(
+        image.resize_exact(shape.width, shape.height, FilterType::Triangle),
+        shape.color,
+    );
+
+    let format =
+        ImageFormat::from_path(name).map_err(|_| "has no known image extension".to_owned())?;
+    let mut out = Vec::new();
+    image
+        .write_to(&mut out, format)
+        .map_err(|e| format!("can't be written as {:?}: {}", format, e))?;
+    Ok(out)
+}
+
+/// Decode the wanted image assets and get their size and colour format,
+/// or why they can't be decoded
+fn shapes(
+    pack: &ResourcePack,
+    wanted: &BTreeSet<String>,
+) -> Option<BTreeMap<String, Result<ImageShape, String>>> {
+    let mut out = BTreeMap::new();
+
+    pack.for_each_file(|file, reader| {
+        let asset = match file.asset().map(ledger::asset_key) {
+            Some(i) if wanted.contains(&i) => i,
+            _ => return Some(()),
+        };
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).ok()?;
+        let shape = image::load_from_memory(&data)
+            .map(|i| {
+                let (width, height) = i.dimensions();
+                ImageShape {
+                    width,
+                    height,
+                    color: i.color(),
+                }
+            })
+            .map_err(|e| e.to_string());
+        out.insert(asset, shape);
+        Some(())
+    })?;
+
+    Some(out)
+}
+
+fn convert(image: DynamicImage, color: ColorType) -> DynamicImage {
+    match color {
+        ColorType::L8 => DynamicImage::ImageLuma8(image.to_luma8()),
+        ColorType::La8 => DynamicImage::ImageLumaA8(image.to_luma_alpha8()),
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(image.to_rgb8()),
+        ColorType::Rgba8 => DynamicImage::ImageRgba8(image.to_rgba8()),
+        ColorType::L16 => DynamicImage::ImageLuma16(image.to_luma16()),
+        ColorType::La16 => DynamicImage::ImageLumaA16(image.to_luma_alpha16()),
+        ColorType::Rgb16 => DynamicImage::ImageRgb16(image.to_rgb16()),
+        ColorType::Rgba16 => DynamicImage::ImageRgba16(image.to_rgba16()),
+        _ => image,
+    }
+}
+
+impl fmt::Display for ImageShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{} {:?}", self.width, self.height, self.color)
+    }
+}
+
+impl fmt::Display for ImageMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.found, &self.error) {
+            (Some(i), None) => write!(
+                f,
+                "{} is {}, the game's is {}",
+                self.asset, i, self.expected
+            ),
+            (Some(i), Some(e)) => write!(
+                f,
+                "{} is {}, the game's is {}, but it {}",
+                self.asset, i, self.expected, e
+            ),
+            (None, e) => write!(
+                f,
+                "{} can't be decoded: {}",
+                self.asset,
+                e.as_deref().unwrap_or("unknown error")
+            ),
+        }
+    }
+}
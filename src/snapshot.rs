@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::ledger::{self, Ledger};
+use crate::resource_pack::{self, Compression};
+
+pub const FINGERPRINT_FILE: &str = "default_fingerprint.json";
+
+/// What the game's original files looked like when `default.tar` was made
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Hash of Freeways.exe
+    pub exe: Option<String>,
+    /// Hash of every original file in the data folder, by ledger key
+    pub files: BTreeMap<String, String>,
+}
+
+/// How the game differs from the fingerprint of `default.tar`
+#[derive(Debug, Default, Clone)]
+pub struct GameChanges {
+    pub exe: bool,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Fingerprint {
+    /// Fingerprint the game's original files.
+    /// Files installed by packs are counted as the originals they replaced, kept by the ledger.
+    pub fn take(path: &Path, game_path: &Path) -> Option<Fingerprint> {
+        let files = originals(path, game_path, None)?;
+        Some(fingerprint(game_path, &files))
+    }
+
+    pub fn load(path: &Path) -> Option<Fingerprint> {
+        serde_json::from_slice(&fs::read(path.join(FINGERPRINT_FILE)).ok()?).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Option<()> {
+        fs::write(
+            path.join(FINGERPRINT_FILE),
+            serde_json::to_vec_pretty(self).ok()?,
+        )
+        .ok()
+    }
+}
+
+impl GameChanges {
+    pub fn any(&self) -> bool {
+        self.exe || !self.changed.is_empty() || !self.removed.is_empty()
+    }
+}
+
+/// Check if the game has changed since `default.tar` was made, like after an update.
+/// Only Freeways.exe and the fingerprinted files no pack has replaced are checked,
+/// so files added by hand or edited under a pack don't count.
+/// Snapshots made before fingerprints were kept are fingerprinted now and trusted as is.
+pub fn check(path: &Path, game_path: &Path) -> Option<GameChanges> {
+    let old = match Fingerprint::load(path) {
+        Some(i) => i,
+        None => {
+            Fingerprint::take(path, game_path)?.save(path)?;
+            return None;
+        }
+    };
+
+    let data_path = game_path.join("data");
+    let ledger = Ledger::load(path, game_path);
+    let mut out = GameChanges {
+        exe: old.exe != ledger::hash_file(&game_path.join("Freeways.exe")),
+        ..GameChanges::default()
+    };
+
+    for (key, hash) in &old.files {
+        if ledger.files.get(key).map_or(false, |x| !x.is_empty()) {
+            continue;
+        }
+
+        match ledger::hash_file(&data_path.join(key)) {
+            Some(i) if i == *hash => {}
+            Some(_) => out.changed.push(key.to_owned()),
+            None => out.removed.push(key.to_owned()),
+        }
+    }
+
+    Some(out).filter(|x| x.any())
+}
+
+/// Snapshot the game's original files into `default.tar`, and save their fingerprint
+pub fn write_default(path: &Path, game_path: &Path, compression: Compression) -> Option<()> {
+    let (temp, fingerprint) = write_temp(path, game_path, compression)?;
+    if fs::rename(&temp, default_path(path)).is_err() {
+        fs::remove_file(&temp).unwrap_or_default();
+        return None;
+    }
+
+    fingerprint.save(path)
+}
+
+/// Snapshot the game again, then move the old `default.tar` and its fingerprint into the
+/// snapshots folder. Returns the name the old snapshot was archived under.
+/// If the new snapshot can't be made the old one is left in place.
+pub fn rebuild_default(path: &Path, game_path: &Path, compression: Compression) -> Option<String> {
+    let archive = path.join("snapshots");
+    fs::create_dir_all(&archive).ok()?;
+
+    let time = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let name = format!("default-{}", time);
+    let (temp, fingerprint) = write_temp(path, game_path, compression)?;
+
+    let old = archive.join(format!("{}.tar", name));
+    if fs::rename(default_path(path), &old).is_err() {
+        fs::remove_file(&temp).unwrap_or_default();
+        return None;
+    }
+    if fs::rename(&temp, default_path(path)).is_err() {
+        fs::rename(&old, default_path(path)).unwrap_or_default();
+        fs::remove_file(&temp).unwrap_or_default();
+        return None;
+    }
+
+    if path.join(FINGERPRINT_FILE).exists()
+        && fs::rename(
+            path.join(FINGERPRINT_FILE),
+            archive.join(format!("{}.json", name)),
+        )
+        .is_err()
+    {
+        println!("[!] Failed to archive the old fingerprint");
+    }
+
+    fingerprint.save(path)?;
+    Some(name)
+}
+
+fn default_path(path: &Path) -> PathBuf {
+    path.join("resource_packs").join("default.tar")
+}
+
+/// Snapshot the game's original files into a temporary file next to the resource_packs folder.
+/// Returns the file and the fingerprint of what is in it.
+fn write_temp(
+    path: &Path,
+    game_path: &Path,
+    compression: Compression,
+) -> Option<(PathBuf, Fingerprint)> {
+    let files = originals(path, game_path, Fingerprint::load(path).as_ref())?;
+
+    let temp = path.join("default.tar.part");
+    if write_snapshot(&temp, &files, compression).is_none() {
+        fs::remove_file(&temp).unwrap_or_default();
+        return None;
+    }
+
+    Some((temp, fingerprint(game_path, &files)))
+}
+
+fn write_snapshot(
+    to: &Path,
+    files: &BTreeMap<String, (PathBuf, String)>,
+    compression: Compression,
+) -> Option<()> {
+    // Always named default.tar, the compression is picked up from the file itself
+    let file = File::create(to).ok()?;
+    let mut a = tar::Builder::new(compression.encoder(file).ok()?);
+
+    let data: &[u8] = "; Default Freeways Resources\n; Generated by Freeways-Launcher (https://github.com/Basicprogrammer10/Freeways-Lancher)\nname = Default\nauthor = Captain Games\nversion = 1.0\n".as_bytes();
+    resource_pack::append_file(&mut a, "pack.meta", data)?;
+    for (key, (from, _)) in files {
+        a.append_path_with_name(from, format!("assets/{}", key))
+            .ok()?;
+    }
+    a.into_inner().ok()?.finish().ok()
+}
+
+fn fingerprint(game_path: &Path, files: &BTreeMap<String, (PathBuf, String)>) -> Fingerprint {
+    Fingerprint {
+        exe: ledger::hash_file(&game_path.join("Freeways.exe")),
+        files: files
+            .iter()
+            .map(|(key, (_, hash))| (key.to_owned(), hash.to_owned()))
+            .collect(),
+    }
+}
+
+/// Find where to read the original of every file in the data folder, with its hash.
+/// Files a pack installed are read from the ledger's originals, even if they were edited by hand
+/// since, and files a pack added are left out.
+/// Given the last fingerprint, new files are only counted if Freeways.exe changed too,
+/// otherwise they were added by hand.
+fn originals(
+    path: &Path,
+    game_path: &Path,
+    old: Option<&Fingerprint>,
+) -> Option<BTreeMap<String, (PathBuf, String)>> {
+    let data_path = game_path.join("data");
+    let ledger = Ledger::load(path, game_path);
+    let exe = ledger::hash_file(&game_path.join("Freeways.exe"));
+
+    let mut out = BTreeMap::new();
+    for i in resource_pack::list_files(&data_path).ok()? {
+        let key = ledger::asset_key(&i);
+
+        if let Some(bottom) = ledger.files.get(&key).and_then(|x| x.first()) {
+            if let Some(original) = &bottom.replaced {
+                out.insert(key, (ledger.original(original), original.to_owned()));
+            }
+            continue;
+        }
+
+        if old.map_or(false, |x| x.exe == exe && !x.files.contains_key(&key)) {
+            continue;
+        }
+
+        let file = data_path.join(&i);
+        out.insert(key, (file.to_owned(), ledger::hash_file(&file)?));
+    }
+
+    Some(out)
+}
+
+impl fmt::Display for GameChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if self.exe {
+            parts.push("Freeways.exe changed".to_owned());
+        }
+        for (kind, files) in [("changed", &self.changed), ("removed", &self.removed)] {
+            if !files.is_empty() {
+                parts.push(format!("{} file(s) {}", files.len(), kind));
+            }
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
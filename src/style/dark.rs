@@ -1,13 +1,16 @@
 use iced::{
-    button, checkbox, container, slider, slider::Handle, slider::HandleShape, text_input,
-    Background, Color, Vector,
+    button, checkbox, container, pick_list, scrollable, slider, slider::Handle,
+    slider::HandleShape, text_input, Background, Color, Vector,
 };
+use iced_style::menu;
 
 pub struct Container;
 pub struct TextInput;
 pub struct Button;
 pub struct Slider;
 pub struct Checkbox;
+pub struct PickList;
+pub struct Scrollable;
 
 impl container::StyleSheet for Container {
     fn style(&self) -> container::Style {
@@ -114,3 +117,61 @@ impl checkbox::StyleSheet for Checkbox {
         }
     }
 }
+
+impl pick_list::StyleSheet for PickList {
+    fn menu(&self) -> menu::Style {
+        menu::Style {
+            text_color: Color::from_rgb8(242, 245, 252),
+            background: Background::Color(Color::from_rgb8(41, 46, 57)),
+            border_width: 1.0,
+            border_color: Color::from_rgb8(33, 37, 46),
+            selected_text_color: Color::WHITE,
+            selected_background: Background::Color(Color::from_rgb8(59, 66, 82)),
+        }
+    }
+
+    fn active(&self) -> pick_list::Style {
+        pick_list::Style {
+            text_color: Color::from_rgb8(242, 245, 252),
+            background: Background::Color(Color::from_rgb8(41, 46, 57)),
+            border_radius: 5.0,
+            border_width: 1.0,
+            border_color: Color::from_rgb8(33, 37, 46),
+            icon_size: 0.7,
+        }
+    }
+
+    fn hovered(&self) -> pick_list::Style {
+        pick_list::Style {
+            background: Background::Color(Color::from_rgb8(48, 54, 66)),
+            ..self.active()
+        }
+    }
+}
+
+impl scrollable::StyleSheet for Scrollable {
+    fn active(&self) -> scrollable::Scrollbar {
+        scrollable::Scrollbar {
+            background: Some(Background::Color(Color::from_rgb8(41, 46, 57))),
+            border_radius: 4.0,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+            scroller: scrollable::Scroller {
+                color: Color::from_rgb8(33, 37, 46),
+                border_radius: 4.0,
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            },
+        }
+    }
+
+    fn hovered(&self) -> scrollable::Scrollbar {
+        scrollable::Scrollbar {
+            scroller: scrollable::Scroller {
+                color: Color::from_rgb8(59, 66, 82),
+                ..self.active().scroller
+            },
+            ..self.active()
+        }
+    }
+}
@@ -3,6 +3,8 @@ use std::fmt::Display;
 use iced::button;
 use iced::checkbox;
 use iced::container;
+use iced::pick_list;
+use iced::scrollable;
 use iced::slider;
 use iced::text_input;
 
@@ -98,3 +100,21 @@ impl From<Theme> for Box<dyn checkbox::StyleSheet> {
         }
     }
 }
+
+impl From<Theme> for Box<dyn pick_list::StyleSheet> {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::Dark => dark::PickList.into(),
+            Theme::Light => Default::default(),
+        }
+    }
+}
+
+impl From<Theme> for Box<dyn scrollable::StyleSheet> {
+    fn from(theme: Theme) -> Self {
+        match theme {
+            Theme::Dark => dark::Scrollable.into(),
+            Theme::Light => Default::default(),
+        }
+    }
+}
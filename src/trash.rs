@@ -0,0 +1,170 @@
+use std::cmp::Reverse;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::worlds::{self, in_save_dirs, World};
+
+/// Folder in the launcher's data folder that deleted worlds are moved to
+pub const DELETED_FOLDER: &str = "deleted";
+
+/// A world in the deleted folder, with where it came from.
+/// Kept as `<id>.json` next to the world's save.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeletedWorld {
+    pub name: String,
+    /// Where the world was before it was deleted
+    pub original: PathBuf,
+    /// Unix time the world was deleted
+    pub deleted: u64,
+
+    /// The world's save in the deleted folder
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+/// Move a world into the deleted folder, recording where it was and when
+pub fn delete_world(path: &Path, world: &World) -> Result<DeletedWorld, String> {
+    let dir = path.join(DELETED_FOLDER);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to make the deleted folder: {}", e))?;
+
+    let deleted = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs();
+    let mut id = format!("{}-{}", world.name, deleted);
+    let mut n = 1;
+    while dir.join(format!("{}.json", id)).exists() {
+        n += 1;
+        id = format!("{}-{}-{}", world.name, deleted, n);
+    }
+
+    let out = DeletedWorld {
+        name: world.name.to_owned(),
+        original: world.path.to_owned(),
+        deleted,
+        path: dir.join(format!("{}.{}", id, worlds::WORLD_EXT)),
+    };
+    let info = serde_json::to_vec_pretty(&out).map_err(|e| e.to_string())?;
+    fs::write(dir.join(format!("{}.json", id)), info)
+        .map_err(|e| format!("Failed to record the deleted world: {}", e))?;
+
+    if let Err(e) = move_file(&world.path, &out.path) {
+        fs::remove_file(dir.join(format!("{}.json", id))).unwrap_or_default();
+        return Err(format!("Failed to move {}: {}", world.name, e));
+    }
+
+    Ok(out)
+}
+
+/// Get every world in the deleted folder, most recently deleted first
+pub fn deleted_worlds(path: &Path) -> Vec<DeletedWorld> {
+    let mut out = match fs::read_dir(path.join(DELETED_FOLDER)) {
+        Ok(i) => i
+            .filter_map(|x| x.ok())
+            .map(|x| x.path())
+            .filter(|x| x.extension().map_or(false, |x| x == "json"))
+            .filter_map(|x| {
+                let mut world = serde_json::from_slice::<DeletedWorld>(&fs::read(&x).ok()?).ok()?;
+                world.path = x.with_extension(worlds::WORLD_EXT);
+                Some(world)
+            })
+            .collect::<Vec<_>>(),
+        Err(_) => return Vec::new(),
+    };
+
+    out.sort_by_key(|x| Reverse(x.deleted));
+    out
+}
+
+impl DeletedWorld {
+    /// Move the world back to where it was deleted from, if that is still one of `save_dirs`.
+    /// Worlds aren't restored over a world made since with the same name.
+    pub fn restore(&self, save_dirs: &[PathBuf]) -> Result<(), String> {
+        if !in_save_dirs(&self.original, save_dirs) || !worlds::is_world(&self.original) {
+            return Err(format!(
+                "{} isn't a world in the save folders",
+                self.original.to_string_lossy()
+            ));
+        }
+
+        if let Some(i) = self.original.parent() {
+            fs::create_dir_all(i)
+                .map_err(|e| format!("Failed to make {}: {}", i.to_string_lossy(), e))?;
+        }
+        move_file(&self.path, &self.original).map_err(|e| match e.kind() {
+            io::ErrorKind::AlreadyExists => format!(
+                "A world already exists at {}",
+                self.original.to_string_lossy()
+            ),
+            _ => format!("Failed to move {} back: {}", self.name, e),
+        })?;
+        fs::remove_file(self.path.with_extension("json")).unwrap_or_default();
+
+        Ok(())
+    }
+
+    /// Remove the world for good
+    pub fn purge(&self) -> Option<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path).ok()?;
+        }
+        fs::remove_file(self.path.with_extension("json")).ok()
+    }
+
+    pub fn deleted_time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.deleted)
+    }
+}
+
+/// Purge worlds that were deleted more than `days` days ago, 0 keeps them forever.
+/// Returns how many were purged.
+pub fn auto_purge(path: &Path, days: u32) -> usize {
+    if days == 0 {
+        return 0;
+    }
+
+    // A cutoff before the clock can go means nothing is old enough
+    let cutoff =
+        match SystemTime::now().checked_sub(Duration::from_secs(days as u64 * 24 * 60 * 60)) {
+            Some(i) => i,
+            None => return 0,
+        };
+    let mut purged = 0;
+    for i in deleted_worlds(path)
+        .into_iter()
+        .filter(|x| x.deleted_time() < cutoff)
+    {
+        print!("[*] Purging Deleted World ({}) ", i.name);
+        match i.purge() {
+            Some(_) => {
+                println!("[✅]");
+                purged += 1;
+            }
+            None => println!("[❌]"),
+        }
+    }
+
+    purged
+}
+
+/// Move a file without replacing anything already at `to`, which fails with `AlreadyExists`.
+/// It is linked into place, or copied if it is going to another drive.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::hard_link(from, to) {
+        Ok(_) => return fs::remove_file(from),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Err(e),
+        Err(_) => {}
+    }
+
+    let mut file = File::open(from)?;
+    let mut out = OpenOptions::new().write(true).create_new(true).open(to)?;
+    if let Err(e) = io::copy(&mut file, &mut out) {
+        fs::remove_file(to).unwrap_or_default();
+        return Err(e);
+    }
+    fs::remove_file(from)
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::path::PathBuf;
+
+use ed25519_dalek::{Keypair, PublicKey, SecretKey};
+use serde::{Deserialize, Serialize};
+
+pub const TRUST_FILE: &str = "trusted_keys.json";
+pub const KEY_FILE: &str = "signing_key";
+
+/// Public keys of pack authors the user trusts
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrustStore {
+    pub keys: Vec<TrustedKey>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedKey {
+    /// ed25519 public key in hex
+    pub key: String,
+    pub name: String,
+}
+
+impl TrustStore {
+    pub fn load(path: &Path) -> TrustStore {
+        let path = path.join(TRUST_FILE);
+        let store = match fs::read(&path) {
+            Ok(i) => serde_json::from_slice(&i).unwrap_or_else(|_| {
+                println!("[!] Trusted keys file is corrupt, starting a new one");
+                TrustStore::default()
+            }),
+            Err(_) => TrustStore::default(),
+        };
+
+        TrustStore { path, ..store }
+    }
+
+    pub fn save(&self) -> Option<()> {
+        fs::write(&self.path, serde_json::to_vec_pretty(self).ok()?).ok()
+    }
+
+    pub fn get(&self, key: &PublicKey) -> Option<&TrustedKey> {
+        let key = hex::encode(key.to_bytes());
+        self.keys.iter().find(|x| x.key == key)
+    }
+
+    /// Trust a key, naming it after its owner
+    pub fn trust(&mut self, key: &PublicKey, name: &str) {
+        if self.get(key).is_some() {
+            return;
+        }
+
+        self.keys.push(TrustedKey {
+            key: hex::encode(key.to_bytes()),
+            name: name.to_owned(),
+        });
+    }
+
+    pub fn untrust(&mut self, key: &PublicKey) {
+        let key = hex::encode(key.to_bytes());
+        self.keys.retain(|x| x.key != key);
+    }
+}
+
+/// Load the user's key for signing packs, making one the first time it is needed.
+/// New keys are added to the trust store so the user's own packs show as trusted.
+pub fn signing_key(path: &Path) -> Option<Keypair> {
+    let key_path = path.join(KEY_FILE);
+
+    let secret = match fs::read_to_string(&key_path) {
+        Ok(i) => SecretKey::from_bytes(&hex::decode(i.trim()).ok()?).ok()?,
+        Err(_) => {
+            println!("[*] Making a new pack signing key");
+            let secret = Keypair::generate(&mut rand::rngs::OsRng).secret;
+            key_file(&key_path)
+                .ok()?
+                .write_all(hex::encode(secret.to_bytes()).as_bytes())
+                .ok()?;
+
+            let mut trust = TrustStore::load(path);
+            trust.trust(&PublicKey::from(&secret), "You");
+            trust.save()?;
+            secret
+        }
+    };
+
+    Some(Keypair {
+        public: PublicKey::from(&secret),
+        secret,
+    })
+}
+
+/// Make a new key file that only the owner can read, from the moment it exists
+#[cfg(unix)]
+fn key_file(path: &Path) -> io::Result<File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn key_file(path: &Path) -> io::Result<File> {
+    OpenOptions::new().write(true).create_new(true).open(path)
+}
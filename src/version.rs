@@ -0,0 +1,180 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A dotted version number like `1.2.3`.
+/// Missing parts count as 0, so `1.2` is the same as `1.2.0`.
+#[derive(Debug, Clone)]
+pub struct Version(Vec<u32>);
+
+/// A set of versions, written as `*`, `1.2`, `1.2+` or `1.2 - 1.4`.
+/// Config values can't hold `=`, so there are no `>=` style operators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionRange {
+    Any,
+    Exact(Version),
+    AtLeast(Version),
+    Between(Version, Version),
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Option<Version> {
+        let text = text.trim();
+        let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+
+        text.split('.')
+            .map(|x| x.parse().ok())
+            .collect::<Option<Vec<_>>>()
+            .map(Version)
+    }
+}
+
+impl VersionRange {
+    pub fn parse(text: &str) -> Option<VersionRange> {
+        let text = text.trim();
+
+        if text == "*" {
+            return Some(VersionRange::Any);
+        }
+
+        if let Some(i) = text.strip_suffix('+') {
+            return Some(VersionRange::AtLeast(Version::parse(i)?));
+        }
+
+        if let Some((min, max)) = text.split_once(" - ") {
+            let (min, max) = (Version::parse(min)?, Version::parse(max)?);
+            if min > max {
+                return None;
+            }
+            return Some(VersionRange::Between(min, max));
+        }
+
+        Some(VersionRange::Exact(Version::parse(text)?))
+    }
+
+    pub fn contains(&self, version: &Version) -> bool {
+        match self {
+            VersionRange::Any => true,
+            VersionRange::Exact(i) => version == i,
+            VersionRange::AtLeast(i) => version >= i,
+            VersionRange::Between(min, max) => version >= min && version <= max,
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.0.len().max(other.0.len());
+        let part = |v: &Version, i| v.0.get(i).copied().unwrap_or(0);
+
+        (0..len)
+            .map(|i| part(self, i).cmp(&part(other, i)))
+            .find(|x| *x != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Version {}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = self.0.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        f.write_str(&parts.join("."))
+    }
+}
+
+impl fmt::Display for VersionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionRange::Any => f.write_str("*"),
+            VersionRange::Exact(i) => write!(f, "{}", i),
+            VersionRange::AtLeast(i) => write!(f, "{}+", i),
+            VersionRange::Between(min, max) => write!(f, "{} - {}", min, max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(v("1.2"), v("1.2.0"));
+        assert_eq!(v("v1.2"), v("1.2"));
+        assert!(v("1.10") > v("1.9"));
+        assert!(Version::parse("1.x").is_none());
+        assert!(Version::parse("").is_none());
+        assert!(Version::parse("1..2").is_none());
+    }
+
+    #[test]
+    fn any() {
+        let range = VersionRange::parse(" * ").unwrap();
+        assert_eq!(range, VersionRange::Any);
+        assert!(range.contains(&v("0.1")));
+        assert_eq!(range.to_string(), "*");
+    }
+
+    #[test]
+    fn exact() {
+        let range = VersionRange::parse("1.2").unwrap();
+        assert_eq!(range, VersionRange::Exact(v("1.2")));
+        assert!(range.contains(&v("1.2.0")));
+        assert!(!range.contains(&v("1.2.1")));
+        assert_eq!(range.to_string(), "1.2");
+    }
+
+    #[test]
+    fn at_least() {
+        let range = VersionRange::parse("1.2+").unwrap();
+        assert_eq!(range, VersionRange::AtLeast(v("1.2")));
+        assert!(range.contains(&v("1.2")));
+        assert!(range.contains(&v("3")));
+        assert!(!range.contains(&v("1.1.9")));
+        assert_eq!(range.to_string(), "1.2+");
+    }
+
+    #[test]
+    fn between() {
+        let range = VersionRange::parse("1.2 - 1.4").unwrap();
+        assert_eq!(range, VersionRange::Between(v("1.2"), v("1.4")));
+        assert!(range.contains(&v("1.2")));
+        assert!(range.contains(&v("1.3.5")));
+        assert!(range.contains(&v("1.4")));
+        assert!(!range.contains(&v("1.4.1")));
+        assert_eq!(range.to_string(), "1.2 - 1.4");
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        for i in [
+            "",
+            "+",
+            "1.2 -",
+            "- 1.2",
+            "1.4 - 1.2",
+            "1.2-1.4",
+            ">=1.2",
+            "1.x+",
+            "**",
+        ] {
+            assert!(VersionRange::parse(i).is_none(), "{}", i);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::resource_pack::{self, Compression, PackFormat};
+use crate::trash::{self, DeletedWorld};
+use crate::worlds::{self, World};
+use crate::VERSION;
+
+/// Folder in the launcher's data folder that exported worlds are written to
+pub const EXPORT_FOLDER: &str = "exports";
+pub const ARCHIVE_EXT: &str = ".world.tar.gz";
+const INFO_FILE: &str = "world.json";
+const WORLD_FILE: &str = "world.sav";
+
+/// What an exported world was made with, kept as the first file in the archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldInfo {
+    pub name: String,
+    /// Version of the launcher that exported it
+    pub launcher_version: String,
+    /// Names of the resource packs that were enabled, highest priority first
+    pub resource_packs: Vec<String>,
+    pub note: Option<String>,
+    /// Unix time the world was exported
+    pub exported: u64,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Io(io::Error),
+    Invalid(String),
+    /// A world with the same name exists, it is only replaced if asked to
+    Exists(String),
+}
+
+/// Bundle a world with its info into an archive in the exports folder.
+/// The archive is written next to any earlier export of the world first, and only replaces it once
+/// it is complete. Returns where the archive was written.
+pub fn export_world(
+    path: &Path,
+    world: &World,
+    resource_packs: Vec<String>,
+    note: &str,
+) -> Result<PathBuf, String> {
+    let dir = path.join(EXPORT_FOLDER);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to make the exports folder: {}", e))?;
+
+    let info = WorldInfo {
+        name: world.name.to_owned(),
+        launcher_version: VERSION.to_owned(),
+        resource_packs,
+        note: Some(note.trim().to_owned()).filter(|x| !x.is_empty()),
+        exported: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs(),
+    };
+
+    let to = dir.join(format!("{}{}", world.name, ARCHIVE_EXT));
+    let temp = dir.join(format!("{}{}.part", world.name, ARCHIVE_EXT));
+    if write_archive(&temp, &info, &world.path).is_none() {
+        fs::remove_file(&temp).unwrap_or_default();
+        return Err(format!("Failed to write {}", to.to_string_lossy()));
+    }
+    if let Err(e) = fs::rename(&temp, &to) {
+        fs::remove_file(&temp).unwrap_or_default();
+        return Err(format!("Failed to write {}: {}", to.to_string_lossy(), e));
+    }
+
+    Ok(to)
+}
+
+fn write_archive(to: &Path, info: &WorldInfo, world: &Path) -> Option<()> {
+    let file = File::create(to).ok()?;
+    let mut a = tar::Builder::new(Compression::Gzip.encoder(file).ok()?);
+
+    resource_pack::append_file(&mut a, INFO_FILE, &serde_json::to_vec_pretty(info).ok()?)?;
+    a.append_path_with_name(world, WORLD_FILE).ok()?;
+    a.into_inner().ok()?.finish().ok()
+}
+
+/// Read the info of an exported world, checking the archive holds a world
+pub fn read_info(archive: &Path) -> Result<WorldInfo, ImportError> {
+    let mut info = None;
+    walk_archive(archive, |name, reader| {
+        if name == INFO_FILE {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            info = Some(data);
+        }
+        Ok(())
+    })?;
+
+    parse_info(&info.unwrap_or_default())
+}
+
+/// Install an exported world into `dir` under the name it was exported with.
+/// With `replace` set an existing world of the same name is moved to the deleted folder once the
+/// new one is extracted, otherwise it is left alone and [`ImportError::Exists`] is returned.
+pub fn import_world(
+    path: &Path,
+    archive: &Path,
+    dir: &Path,
+    replace: bool,
+) -> Result<(World, WorldInfo), ImportError> {
+    let info = read_info(archive)?;
+    let to = worlds::world_path(dir, info.name.trim()).map_err(ImportError::Invalid)?;
+
+    if to.exists() && !replace {
+        return Err(ImportError::Exists(info.name));
+    }
+
+    // Extracted next to the world first, so a bad archive doesn't cost the world it replaces
+    fs::create_dir_all(dir)?;
+    let temp = to.with_extension(format!("{}.part", worlds::WORLD_EXT));
+    let result = walk_archive(archive, |name, reader| {
+        if name == WORLD_FILE {
+            io::copy(reader, &mut File::create(&temp)?)?;
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        fs::remove_file(&temp).unwrap_or_default();
+        return Err(e);
+    }
+
+    let mut replaced = None;
+    if to.exists() {
+        match replace_world(path, &to) {
+            Ok(i) => replaced = Some(i),
+            Err(e) => {
+                fs::remove_file(&temp).unwrap_or_default();
+                return Err(e);
+            }
+        }
+    }
+    if let Err(e) = fs::rename(&temp, &to) {
+        fs::remove_file(&temp).unwrap_or_default();
+        if let Some(i) = replaced {
+            i.restore(&[dir.to_path_buf()]).unwrap_or_default();
+        }
+        return Err(e.into());
+    }
+
+    let world = World::load(to)
+        .ok_or_else(|| ImportError::Invalid("Failed to read the imported world".to_owned()))?;
+    Ok((world, info))
+}
+
+/// Move the world at `to` to the deleted folder, to make room for an imported one
+fn replace_world(path: &Path, to: &Path) -> Result<DeletedWorld, ImportError> {
+    let old = World::load(to.to_owned())
+        .ok_or_else(|| ImportError::Invalid(format!("{} is not a world", to.to_string_lossy())))?;
+    trash::delete_world(path, &old).map_err(ImportError::Invalid)
+}
+
+/// Stream each file in an archive to `f`, refusing anything that isn't a world
+fn walk_archive(
+    archive: &Path,
+    mut f: impl FnMut(&str, &mut dyn Read) -> Result<(), ImportError>,
+) -> Result<(), ImportError> {
+    let compression = match PackFormat::detect(archive) {
+        Some(PackFormat::Tar(i)) => i,
+        _ => {
+            return Err(ImportError::Invalid(format!(
+                "{} isn't a tar archive",
+                archive.to_string_lossy()
+            )))
+        }
+    };
+    let file = File::open(archive)?;
+    let mut archive = tar::Archive::new(compression.decoder(file)?);
+
+    let mut found = (false, false);
+    for i in archive.entries()? {
+        let mut i = i?;
+        let name = i.path()?.to_string_lossy().to_string();
+        if !i.header().entry_type().is_file() || (name != INFO_FILE && name != WORLD_FILE) {
+            return Err(ImportError::Invalid(format!(
+                "'{}' doesn't belong in an exported world",
+                name
+            )));
+        }
+
+        found = (found.0 || name == INFO_FILE, found.1 || name == WORLD_FILE);
+        f(&name, &mut i)?;
+    }
+
+    match found {
+        (true, true) => Ok(()),
+        (false, _) => Err(ImportError::Invalid(format!("{} is missing", INFO_FILE))),
+        (_, false) => Err(ImportError::Invalid(format!("{} is missing", WORLD_FILE))),
+    }
+}
+
+fn parse_info(data: &[u8]) -> Result<WorldInfo, ImportError> {
+    let info = serde_json::from_slice::<WorldInfo>(data)
+        .map_err(|e| ImportError::Invalid(format!("{} is invalid: {}", INFO_FILE, e)))?;
+    if info.name.trim().is_empty() {
+        return Err(ImportError::Invalid(format!(
+            "{} has no world name",
+            INFO_FILE
+        )));
+    }
+
+    Ok(info)
+}
+
+impl From<io::Error> for ImportError {
+    fn from(e: io::Error) -> Self {
+        ImportError::Io(e)
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io(e) => write!(f, "{}", e),
+            ImportError::Invalid(e) => f.write_str(e),
+            ImportError::Exists(i) => write!(f, "A world named {} already exists", i),
+        }
+    }
+}
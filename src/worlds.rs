@@ -0,0 +1,254 @@
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local};
+
+use crate::ledger;
+use crate::resource_pack::list_files;
+
+/// Folder in the game's install that holds its saves, used when `save_path` isn't set.
+/// The game doesn't document where it saves, this is only the layout the launcher has assumed
+/// since it first managed worlds, so `save_path` should be set to the folder the game really uses.
+pub const SAVE_FOLDER: &str = "saves";
+/// Save the game loads on start, in the save folder. The active world is copied here before launching
+pub const ACTIVE_SAVE: &str = "current.sav";
+/// Folder in the launcher's data folder the active save is backed up to before a world is loaded
+pub const ACTIVE_BACKUP_FOLDER: &str = "active_saves";
+/// Extension of world save files
+pub const WORLD_EXT: &str = "sav";
+
+/// A world save found in one of the save folders
+#[derive(Debug, Clone)]
+pub struct World {
+    pub name: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl World {
+    pub fn load(path: PathBuf) -> Option<World> {
+        let meta = fs::metadata(&path).ok()?;
+        if !meta.is_file() || !is_world(&path) {
+            return None;
+        }
+
+        Some(World {
+            name: path.file_stem()?.to_string_lossy().to_string(),
+            size: meta.len(),
+            modified: meta.modified().ok(),
+            path,
+        })
+    }
+
+    /// Size in a short readable form, like `1.2 MB`
+    pub fn size_text(&self) -> String {
+        size_text(self.size)
+    }
+
+    /// Last modified time in local time, like `2021-07-14 18:03`
+    pub fn modified_text(&self) -> String {
+        match self.modified {
+            Some(i) => time_text(i),
+            None => "Unknown".to_owned(),
+        }
+    }
+}
+
+/// Folders to look for worlds in, the game's save folder and the user's own save folder if set
+pub fn save_dirs(game_path: &Path, save_path: Option<&Path>) -> Vec<PathBuf> {
+    let mut out = vec![game_path.join(SAVE_FOLDER)];
+    if let Some(i) = save_path {
+        if !out.contains(&i.to_path_buf()) {
+            out.push(i.to_path_buf());
+        }
+    }
+
+    out
+}
+
+/// Check a file is in one of the save folders, so nothing is written anywhere else
+pub fn in_save_dirs(file: &Path, save_dirs: &[PathBuf]) -> bool {
+    !file.components().any(|x| x == Component::ParentDir)
+        && save_dirs.iter().any(|x| file.starts_with(x))
+}
+
+/// Find every world in the save folders, most recently played first
+pub fn find_worlds(dirs: &[PathBuf]) -> Vec<World> {
+    let mut worlds = dirs
+        .iter()
+        .filter_map(|x| fs::read_dir(x).ok())
+        .flatten()
+        .filter_map(|x| World::load(x.ok()?.path()))
+        .collect::<Vec<_>>();
+
+    worlds.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.name.cmp(&b.name)));
+    worlds
+}
+
+/// Every file in the save folders, for backing them all up
+pub fn save_files(dirs: &[PathBuf]) -> Vec<PathBuf> {
+    dirs.iter()
+        .filter_map(|x| Some(list_files(x).ok()?.into_iter().map(move |i| x.join(i))))
+        .flatten()
+        .collect()
+}
+
+/// Folder the game saves to, `save_path` from the config or else `SAVE_FOLDER` in the game's install.
+/// The game's active save is here, and new worlds are made here.
+pub fn save_folder(game_path: &Path, save_path: Option<&Path>) -> PathBuf {
+    match save_path {
+        Some(i) => i.to_path_buf(),
+        None => game_path.join(SAVE_FOLDER),
+    }
+}
+
+/// The save the game loads on start
+pub fn active_save(game_path: &Path, save_path: Option<&Path>) -> PathBuf {
+    save_folder(game_path, save_path).join(ACTIVE_SAVE)
+}
+
+/// Make a new world in `dir`, copied from a template save or empty.
+/// An empty world has no progress yet, so the game starts it from scratch.
+pub fn create_world(dir: &Path, name: &str, template: Option<&Path>) -> Result<World, String> {
+    let name = name.trim();
+    let path = world_path(dir, name)?;
+
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to make {}: {}", dir.to_string_lossy(), e))?;
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(i) => i,
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            return Err(format!("A world named {} already exists", name))
+        }
+        Err(e) => return Err(format!("Failed to write the world: {}", e)),
+    };
+
+    if let Some(i) = template {
+        if let Err(e) = File::open(i).and_then(|mut x| io::copy(&mut x, &mut file)) {
+            fs::remove_file(&path).unwrap_or_default();
+            return Err(format!("Failed to write the world: {}", e));
+        }
+    }
+
+    World::load(path).ok_or_else(|| "Failed to read the new world".to_owned())
+}
+
+/// Where a world with this name goes in `dir`, or why the name can't be used
+pub fn world_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() {
+        return Err("A name is needed".to_owned());
+    }
+    if name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']) || name.starts_with('.') {
+        return Err(format!("'{}' can't be used as a file name", name));
+    }
+
+    let path = dir.join(format!("{}.{}", name, WORLD_EXT));
+    if !is_world(&path) {
+        return Err(format!("'{}' is the game's active save", name));
+    }
+
+    Ok(path)
+}
+
+/// Copy a world into `active`, the save the game loads on start.
+/// For an empty world the active save is removed, so the game starts a new one.
+/// Unless it already holds the world, the active save is backed up into `backup_dir` first,
+/// and the world isn't loaded if that fails.
+pub fn activate(world: &Path, active: &Path, backup_dir: &Path) -> Result<(), String> {
+    if let Some(slot) = active.parent() {
+        fs::create_dir_all(slot)
+            .map_err(|e| format!("Failed to make {}: {}", slot.to_string_lossy(), e))?;
+    }
+
+    if active.exists() && ledger::hash_file(active) != ledger::hash_file(world) {
+        let to = backup_active(active, backup_dir)
+            .map_err(|e| format!("Failed to back up the active save: {}", e))?;
+        println!("[*] Backed up the active save to {}", to.to_string_lossy());
+    }
+
+    let empty = fs::metadata(world)
+        .map_err(|e| format!("Failed to read the world: {}", e))?
+        .len()
+        == 0;
+    if empty {
+        if active.exists() {
+            fs::remove_file(active)
+                .map_err(|e| format!("Failed to remove the active save: {}", e))?;
+        }
+        return Ok(());
+    }
+
+    fs::copy(world, active).map_err(|e| format!("Failed to load the world: {}", e))?;
+    Ok(())
+}
+
+/// Copy the active save to a new `current-<time>.sav` in `dir`
+fn backup_active(active: &Path, dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+
+    let mut n = 1;
+    loop {
+        let name = match n {
+            1 => format!("current-{}.{}", time, WORLD_EXT),
+            _ => format!("current-{}-{}.{}", time, n, WORLD_EXT),
+        };
+        let to = dir.join(name);
+        match OpenOptions::new().write(true).create_new(true).open(&to) {
+            Ok(mut file) => {
+                io::copy(&mut File::open(active)?, &mut file)?;
+                return Ok(to);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Copy the game's progress in `active` back into the active world after it closes
+pub fn store(world: &Path, active: &Path) -> Option<()> {
+    if !active.exists() {
+        return Some(());
+    }
+
+    fs::copy(active, world).ok()?;
+    Some(())
+}
+
+/// Check if a file is a world save, and not the game's active save
+pub fn is_world(path: &Path) -> bool {
+    let ext = path.extension().map(|x| x.to_string_lossy().to_lowercase());
+    ext.as_deref() == Some(WORLD_EXT)
+        && path.file_name().map_or(false, |x| {
+            !x.to_string_lossy().eq_ignore_ascii_case(ACTIVE_SAVE)
+        })
+}
+
+/// A time in local time, like `2021-07-14 18:03`
+pub fn time_text(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+pub fn size_text(size: u64) -> String {
+    let units = ["B", "KB", "MB", "GB"];
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", size, units[unit])
+    } else {
+        format!("{:.1} {}", size, units[unit])
+    }
+}